@@ -0,0 +1,167 @@
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+const CACHE_FILENAME: &str = ".fvp-unpacker-cache.json";
+
+/// The entries unpacked into an output directory, to tell which entries have been unpacked and
+/// which have changed since the last run.
+#[derive(Default, Serialize, Deserialize)]
+pub struct UnpackCache {
+  /// The options which change the output, every entry is changed if they differ
+  options: String,
+  entries: BTreeMap<String, CachedEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedEntry {
+  hash: String,
+  /// The files written for the entry, relative to the output directory
+  files: Vec<PathBuf>,
+}
+
+impl UnpackCache {
+  pub fn new(options: impl Into<String>) -> Self {
+    Self {
+      options: options.into(),
+      entries: BTreeMap::new(),
+    }
+  }
+
+  /// Load the cache in `dir`, which is empty if there is none, or it was written with other
+  /// options.
+  pub fn load(dir: &Path, options: &str) -> Self {
+    let path = dir.join(CACHE_FILENAME);
+
+    let Ok(bytes) = fs::read(&path) else {
+      return Self::default();
+    };
+
+    match serde_json::from_slice::<Self>(&bytes) {
+      Ok(cache) if cache.options == options => cache,
+      Ok(_) => Self::default(),
+      Err(error) => {
+        warn!("Ignored the invalid cache {}: {error}", path.display());
+        Self::default()
+      }
+    }
+  }
+
+  pub fn save(&self, dir: &Path) -> Result<()> {
+    let path = dir.join(CACHE_FILENAME);
+    let json = serde_json::to_vec(self)?;
+    fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+  }
+
+  pub fn hash(data: &[u8]) -> String {
+    blake3::hash(data).to_hex().to_string()
+  }
+
+  /// Record the files written for an entry, which are relative to the output directory.
+  pub fn insert(&mut self, name: impl Into<String>, hash: impl Into<String>, files: Vec<PathBuf>) {
+    let entry = CachedEntry {
+      hash: hash.into(),
+      files,
+    };
+    self.entries.insert(name.into(), entry);
+  }
+
+  /// The files written for an entry, relative to the output directory.
+  pub fn files(&self, name: &str) -> &[PathBuf] {
+    self
+      .entries
+      .get(name)
+      .map(|entry| entry.files.as_slice())
+      .unwrap_or_default()
+  }
+
+  /// Whether the entry has been unpacked into `dir`, i.e. every file written for it still exists.
+  pub fn is_unpacked(&self, dir: &Path, name: &str) -> bool {
+    self
+      .entries
+      .get(name)
+      .is_some_and(|entry| entry.files.iter().all(|file| dir.join(file).exists()))
+  }
+
+  /// Whether the entry has been unpacked into `dir` from the same data.
+  pub fn is_unchanged(&self, dir: &Path, name: &str, hash: &str) -> bool {
+    self.entries.get(name).is_some_and(|x| x.hash == hash) && self.is_unpacked(dir, name)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// An empty directory for a test.
+  fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fvp-unpacker-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn cache_with_entry(dir: &Path) -> UnpackCache {
+    fs::write(dir.join("bg01-0.png"), b"").unwrap();
+    fs::write(dir.join("bg01-1.png"), b"").unwrap();
+
+    let mut cache = UnpackCache::new("atlas");
+    cache.insert(
+      "bg01",
+      UnpackCache::hash(b"bg01"),
+      vec![PathBuf::from("bg01-0.png"), PathBuf::from("bg01-1.png")],
+    );
+    cache.insert("empty", UnpackCache::hash(b"empty"), Vec::new());
+    cache
+  }
+
+  #[test]
+  fn unchanged_entries() {
+    let dir = test_dir("unchanged");
+    let cache = cache_with_entry(&dir);
+    cache.save(&dir).unwrap();
+
+    let cache = UnpackCache::load(&dir, "atlas");
+    assert!(cache.is_unchanged(&dir, "bg01", &UnpackCache::hash(b"bg01")));
+    assert!(!cache.is_unchanged(&dir, "bg01", &UnpackCache::hash(b"changed")));
+    assert!(!cache.is_unchanged(&dir, "bg02", &UnpackCache::hash(b"bg01")));
+    // an image without any frames writes no files
+    assert!(cache.is_unchanged(&dir, "empty", &UnpackCache::hash(b"empty")));
+
+    fs::remove_file(dir.join("bg01-1.png")).unwrap();
+    assert!(!cache.is_unpacked(&dir, "bg01"));
+    assert!(!cache.is_unchanged(&dir, "bg01", &UnpackCache::hash(b"bg01")));
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn load_with_other_options() {
+    let dir = test_dir("options");
+    cache_with_entry(&dir).save(&dir).unwrap();
+
+    let cache = UnpackCache::load(&dir, "trim");
+    assert!(!cache.is_unpacked(&dir, "bg01"));
+    assert!(cache.files("bg01").is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn load_invalid_cache() {
+    let dir = test_dir("invalid");
+    cache_with_entry(&dir);
+    fs::write(dir.join(CACHE_FILENAME), b"{\"options\": \"atlas\"").unwrap();
+
+    let cache = UnpackCache::load(&dir, "atlas");
+    assert!(!cache.is_unpacked(&dir, "bg01"));
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
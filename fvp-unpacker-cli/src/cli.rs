@@ -1,16 +1,71 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
-use crate::commands::{ListArgs, TachieArgs, UnpackArgs};
+use crate::commands::{
+  AssetsArgs, BgmArgs, CallGraphArgs, CgArgs, DecompileArgs, GrepArgs, InfoArgs, ListArgs,
+  PackArgs, TachieArgs, UnpackArgs, UnpackGameArgs, VerifyArgs, VoiceArgs,
+};
+use crate::error::ErrorFormat;
 
 #[derive(Parser)]
 #[command(version, author, about = "A blazing fast tool to unpack FVP archive", long_about = None)]
-pub enum Cli {
+pub struct Cli {
+  #[command(subcommand)]
+  pub command: Command,
+
+  /// How errors are reported to stderr
+  #[arg(long, global = true, value_enum, default_value_t)]
+  pub error_format: ErrorFormat,
+
+  /// Hide the progress bars and warnings
+  #[arg(short, long, global = true, conflicts_with = "verbose")]
+  pub quiet: bool,
+
+  /// Log every entry processed instead of showing the progress bars
+  #[arg(short, long, global = true)]
+  pub verbose: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
   /// Unpack all files from the archive without additional processing
   Unpack(UnpackArgs),
 
+  /// Unpack every archive(.bin) and decompile the script(.hcb) of a game directory, with a report
+  UnpackGame(UnpackGameArgs),
+
   /// List files that can be unpacked
   List(ListArgs),
 
+  /// Show the information of the images and audio in the archive, e.g. sizes and loop points
+  Info(InfoArgs),
+
   /// Process the original image and output the tachie(立ち絵).
   Tachie(TachieArgs),
+
+  /// Compose the event CGs from their bases and diffs, as shown in the gallery
+  Cg(CgArgs),
+
+  /// Render the background music with its loop repeated and faded out, as WAV or FLAC
+  Bgm(BgmArgs),
+
+  /// Verify the integrity of the Ogg audio in the archive
+  Verify(VerifyArgs),
+
+  /// Pack the archive with the entries replaced by edited Ogg files, checking their formats
+  Pack(PackArgs),
+
+  /// Decompile the script(.hcb) into readable pseudo-code
+  Decompile(DecompileArgs),
+
+  /// Map the voice files to the dialogue lines in the script(.hcb)
+  Voice(VoiceArgs),
+
+  /// Report the images referenced by the script(.hcb)
+  Assets(AssetsArgs),
+
+  /// Export the call graph and the flags read/written by each function of the script(.hcb)
+  Callgraph(CallGraphArgs),
+
+  /// Search entry names of the archives(.bin) and text of the script(.hcb) in a game directory
+  Grep(GrepArgs),
 }
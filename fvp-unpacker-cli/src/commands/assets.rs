@@ -0,0 +1,104 @@
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  path::PathBuf,
+};
+
+use anyhow::Result;
+use clap::Args;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+use fvp_unpacker_core::script::{
+  assets::{DEFAULT_IMAGE_SYSCALLS, unreferenced_entries},
+  decompile::function_name,
+};
+
+use crate::utils::{open_archive, open_script};
+
+#[derive(Args)]
+pub struct AssetsArgs {
+  /// Input script(.hcb) file path
+  #[arg(short, long)]
+  input: PathBuf,
+
+  /// Archives to find unreferenced entries in, e.g. `graph.bin`
+  #[arg(short, long = "archive")]
+  archives: Vec<PathBuf>,
+
+  /// Syscalls which load images [default: GraphLoad]
+  #[arg(long = "syscall")]
+  syscalls: Vec<String>,
+}
+
+pub fn assets(args: &AssetsArgs) -> Result<()> {
+  let hcb = open_script(&args.input)?;
+
+  let syscalls: Vec<&str> = if args.syscalls.is_empty() {
+    DEFAULT_IMAGE_SYSCALLS.to_vec()
+  } else {
+    args.syscalls.iter().map(String::as_str).collect()
+  };
+
+  let references = hcb.decompile().asset_references(&syscalls);
+
+  let mut table = Table::new();
+  table
+    .load_preset(UTF8_FULL_CONDENSED)
+    .set_header(["Function", "Syscall", "Entry", "Indices"]);
+
+  for reference in &references {
+    table.add_row([
+      function_name(reference.function),
+      reference.syscall.clone(),
+      reference.entry_name().to_string(),
+      reference
+        .indices
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", "),
+    ]);
+  }
+
+  println!("Assets referenced by each function:");
+  println!("{table}");
+
+  let mut usages: BTreeMap<&str, (usize, BTreeSet<u32>)> = BTreeMap::new();
+  for reference in &references {
+    let usage = usages.entry(reference.entry_name()).or_default();
+    usage.0 += 1;
+    usage.1.insert(reference.function);
+  }
+
+  let mut table = Table::new();
+  table
+    .load_preset(UTF8_FULL_CONDENSED)
+    .set_header(["Entry", "References", "Functions"]);
+
+  for (entry, (count, functions)) in &usages {
+    table.add_row([
+      entry.to_string(),
+      count.to_string(),
+      functions.len().to_string(),
+    ]);
+  }
+
+  println!("All referenced assets:");
+  println!("{table}");
+
+  for archive in &args.archives {
+    let arc = open_archive(archive)?;
+
+    let mut table = Table::new();
+    table
+      .load_preset(UTF8_FULL_CONDENSED)
+      .set_header(["Filename"]);
+
+    for entry in unreferenced_entries(&arc, &references) {
+      table.add_row([entry.filename()]);
+    }
+
+    println!("Entries never referenced in {}:", archive.display());
+    println!("{table}");
+  }
+
+  Ok(())
+}
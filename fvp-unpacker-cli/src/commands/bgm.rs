@@ -0,0 +1,110 @@
+use std::{
+  fs::{self, File},
+  io::BufWriter,
+  path::PathBuf,
+  time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use clap::{Args, ValueEnum, builder::RangedU64ValueParser};
+use fvp_unpacker_core::audio::{pcm::FvpPcm, vorbis::FvpVorbisInfo};
+use rayon::prelude::*;
+use tracing::warn;
+
+use crate::{
+  error::ArgumentError,
+  utils::{format_duration, open_archive},
+};
+
+#[derive(Clone, Copy, ValueEnum)]
+enum AudioFormat {
+  Wav,
+  Flac,
+}
+
+#[derive(Args)]
+pub struct BgmArgs {
+  /// Input file path, e.g. `bgm.bin`
+  #[arg(short, long)]
+  input: PathBuf,
+
+  /// Output directory path
+  #[arg(short, long, default_value = "./output")]
+  output: PathBuf,
+
+  /// Only render the given entries
+  #[arg(short, long = "entry")]
+  entries: Vec<String>,
+
+  /// How many times the loop is played before fading out, at least once
+  #[arg(short = 'n', long, default_value_t = 2, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+  loops: usize,
+
+  /// Length of the fade out in seconds
+  #[arg(long, default_value_t = 10.0)]
+  fade: f64,
+
+  /// Output format
+  #[arg(short, long, value_enum, default_value_t = AudioFormat::Wav)]
+  format: AudioFormat,
+}
+
+pub fn bgm(args: &BgmArgs) -> Result<()> {
+  if !args.output.is_dir() {
+    fs::create_dir_all(&args.output)?;
+  }
+
+  // TODO: handle other formats
+  let arc = open_archive(&args.input)?;
+
+  for name in &args.entries {
+    if !arc.entries().iter().any(|entry| entry.filename() == name) {
+      bail!(ArgumentError(format!("Can not find entry {name}")));
+    }
+  }
+
+  let fade = Duration::try_from_secs_f64(args.fade)?;
+
+  arc
+    .entries()
+    .par_iter()
+    .filter(|entry| args.entries.is_empty() || args.entries.iter().any(|x| x == entry.filename()))
+    .map(|entry| {
+      let filename = entry.filename();
+
+      let Ok(info) = FvpVorbisInfo::parse(entry.data()) else {
+        warn!("Skipped {filename}, which is not Ogg Vorbis");
+        return Ok(());
+      };
+
+      let pcm = FvpPcm::decode_vorbis(entry.data())
+        .with_context(|| format!("Failed to decode {filename}"))?;
+
+      let pcm = match (info.loop_start(), info.loop_length()) {
+        (Some(start), Some(length)) if length > 0 => pcm.looped(start, length, args.loops, fade),
+        _ => pcm,
+      };
+
+      let extension = match args.format {
+        AudioFormat::Wav => "wav",
+        AudioFormat::Flac => "flac",
+      };
+      let output_path = args.output.join(format!("{filename}.{extension}"));
+      let writer = BufWriter::new(File::create(output_path)?);
+
+      match args.format {
+        AudioFormat::Wav => pcm.write_wav(writer)?,
+        AudioFormat::Flac => pcm.write_flac(writer)?,
+      }
+
+      println!(
+        "Output {filename}.{extension} ({})",
+        format_duration(pcm.duration())
+      );
+
+      Ok(())
+    })
+    .collect::<Result<()>>()?;
+
+  Ok(())
+}
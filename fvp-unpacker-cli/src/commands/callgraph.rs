@@ -0,0 +1,53 @@
+use std::{
+  fs::File,
+  io::{self, BufWriter, Write},
+  path::PathBuf,
+};
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+
+use crate::utils::open_script;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CallGraphFormat {
+  Dot,
+  Json,
+}
+
+#[derive(Args)]
+pub struct CallGraphArgs {
+  /// Input file path
+  #[arg(short, long)]
+  input: PathBuf,
+
+  /// Output file path, print to stdout if not specified
+  #[arg(short, long)]
+  output: Option<PathBuf>,
+
+  /// Output format
+  #[arg(short, long, value_enum, default_value_t = CallGraphFormat::Dot)]
+  format: CallGraphFormat,
+}
+
+pub fn callgraph(args: &CallGraphArgs) -> Result<()> {
+  let hcb = open_script(&args.input)?;
+  let graph = hcb.decompile().call_graph();
+
+  let mut writer: Box<dyn Write> = match &args.output {
+    Some(output) => Box::new(BufWriter::new(File::create(output)?)),
+    None => Box::new(BufWriter::new(io::stdout().lock())),
+  };
+
+  match args.format {
+    CallGraphFormat::Dot => graph.write_dot(&mut writer)?,
+    CallGraphFormat::Json => {
+      serde_json::to_writer_pretty(&mut writer, &graph)?;
+      writeln!(writer)?;
+    }
+  }
+
+  writer.flush()?;
+
+  Ok(())
+}
@@ -0,0 +1,160 @@
+use std::{
+  collections::{HashMap, HashSet},
+  fs,
+  path::PathBuf,
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Args;
+use fvp_unpacker_core::{
+  archive::hzc::FvpHzcEntry,
+  image::{cg::FvpEventCg, compositor::FvpCompositor},
+  prelude::*,
+};
+use rayon::prelude::*;
+use rgb::Bgra;
+
+use crate::{
+  error::ArgumentError,
+  utils::{open_archive, parse_layer, write_bgra_png},
+};
+
+#[derive(Args)]
+pub struct CgArgs {
+  /// Input file path
+  #[arg(short, long)]
+  input: PathBuf,
+
+  /// Output directory path
+  #[arg(short, long, default_value = "./output")]
+  output: PathBuf,
+
+  /// The filename prefix of the event CGs, the diffs are named with a `_` suffix after their base,
+  /// e.g. `EV_001_a` on top of `EV_001`
+  #[arg(long, default_value = "EV_")]
+  prefix: String,
+
+  /// A file mapping each CG to its layers instead of the naming convention, one per line as
+  /// `NAME = BASE + DIFF[:FRAME] + ...`
+  #[arg(short, long)]
+  mapping: Option<PathBuf>,
+
+  /// Treat the images as premultiplied by alpha when compositing
+  #[arg(long)]
+  premultiplied: bool,
+}
+
+fn parse_mapping(mapping: &str) -> Result<Vec<FvpEventCg>> {
+  let mut cgs = Vec::new();
+
+  for (i, line) in mapping.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    let Some((name, layers)) = line.split_once('=') else {
+      bail!(ArgumentError(format!(
+        "Missing `=` at line {} of the mapping file",
+        i + 1
+      )));
+    };
+
+    let layers = layers
+      .split('+')
+      .map(|layer| parse_layer(layer.trim()))
+      .collect::<Result<Vec<_>, _>>()
+      .map_err(|error| ArgumentError(format!("{error} at line {} of the mapping file", i + 1)))?;
+
+    cgs.push(FvpEventCg {
+      name: name.trim().to_string(),
+      layers,
+    });
+  }
+
+  Ok(cgs)
+}
+
+pub fn cg(args: &CgArgs) -> Result<()> {
+  if !args.output.is_dir() {
+    fs::create_dir_all(&args.output)?;
+  }
+
+  // TODO: handle other formats
+  let arc = open_archive(&args.input)?;
+
+  let cgs = match &args.mapping {
+    Some(mapping) => {
+      let mapping = fs::read_to_string(mapping)
+        .with_context(|| format!("Failed to read {}", mapping.display()))?;
+      parse_mapping(&mapping)?
+    }
+    None => arc.event_cgs(&args.prefix),
+  };
+
+  let entries: HashMap<_, _> = arc
+    .entries()
+    .iter()
+    .map(|entry| (entry.filename(), entry))
+    .collect();
+
+  let names: HashSet<_> = cgs
+    .iter()
+    .flat_map(|cg| cg.layers.iter().map(|(name, _)| name.as_str()))
+    .collect();
+
+  let images = names
+    .into_par_iter()
+    .map(|name| {
+      let entry = *entries
+        .get(name)
+        .ok_or(ArgumentError(format!("Can not find layer {name}")))?;
+
+      let images: Vec<_> = match DynamicFvpHzc::parse(entry.data())? {
+        DynamicFvpHzc::Bgr(hzc) => hzc.entries().iter().map(|img| img.to_bgra()).collect(),
+        DynamicFvpHzc::Bgra(hzc) => hzc.entries().to_vec(),
+        DynamicFvpHzc::Gray(_) => bail!("{name} must be BGR or BGRA images"),
+      };
+
+      Ok((name, images))
+    })
+    .collect::<Result<HashMap<_, _>>>()?;
+
+  let alpha = if args.premultiplied {
+    FvpAlpha::Premultiplied
+  } else {
+    FvpAlpha::Straight
+  };
+
+  cgs
+    .par_iter()
+    .map(|cg| {
+      let layer = |(name, frame): &(String, usize)| -> Result<&FvpHzcEntry<Bgra<u8>>> {
+        let frames = &images[name.as_str()];
+        frames.get(*frame).ok_or(anyhow!(ArgumentError(format!(
+          "Layer {name} has only {} images",
+          frames.len()
+        ))))
+      };
+
+      let Some((base, diffs)) = cg.layers.split_first() else {
+        bail!("{} has no layers", cg.name);
+      };
+
+      let base = layer(base)?;
+      let mut compositor = FvpCompositor::new(base.clone()).with_alpha(alpha);
+
+      for diff in diffs {
+        compositor.add_screen_layer(layer(diff)?);
+      }
+
+      let output_path = args.output.join(format!("{}.png", cg.name));
+      write_bgra_png(compositor.image(), &output_path, false)?;
+      Ok(())
+    })
+    .collect::<Result<()>>()?;
+
+  println!("Output {} event CGs", cgs.len());
+
+  Ok(())
+}
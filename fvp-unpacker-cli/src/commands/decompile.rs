@@ -0,0 +1,57 @@
+use std::{
+  fs::File,
+  io::{self, BufWriter, Write},
+  path::PathBuf,
+};
+
+use anyhow::Result;
+use clap::Args;
+use fvp_unpacker_core::script::hcb::FvpHcbOpcode;
+
+use crate::utils::open_script;
+
+#[derive(Args)]
+pub struct DecompileArgs {
+  /// Input file path
+  #[arg(short, long)]
+  input: PathBuf,
+
+  /// Output file path, print to stdout if not specified
+  #[arg(short, long)]
+  output: Option<PathBuf>,
+
+  /// Print the raw disassembly instead of pseudo-code
+  #[arg(long)]
+  disassemble: bool,
+}
+
+pub fn decompile(args: &DecompileArgs) -> Result<()> {
+  let hcb = open_script(&args.input)?;
+
+  let mut writer: Box<dyn Write> = match &args.output {
+    Some(output) => Box::new(BufWriter::new(File::create(output)?)),
+    None => Box::new(BufWriter::new(io::stdout().lock())),
+  };
+
+  if args.disassemble {
+    for function in hcb.functions() {
+      writeln!(writer)?;
+      writeln!(writer, "; function at {:#010x}", function.address)?;
+
+      for instruction in function.instructions {
+        match instruction.opcode {
+          FvpHcbOpcode::Syscall(id) => {
+            writeln!(writer, "{instruction} ; {}", hcb.syscall_name(id))?
+          }
+          _ => writeln!(writer, "{instruction}")?,
+        }
+      }
+    }
+  } else {
+    write!(writer, "{}", hcb.decompile())?;
+  }
+
+  writer.flush()?;
+
+  Ok(())
+}
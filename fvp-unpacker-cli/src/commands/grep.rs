@@ -0,0 +1,85 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::Args;
+use fvp_unpacker_core::script::{decompile::function_name, hcb::FvpHcbOpcode};
+use regex::{Regex, RegexBuilder};
+use tracing::warn;
+
+use crate::utils::{find_files, is_script, open_archive, open_script};
+
+#[derive(Args)]
+pub struct GrepArgs {
+  /// The pattern to search for, a regular expression by default
+  pattern: String,
+
+  /// The game directory to search in
+  game_dir: PathBuf,
+
+  /// Search case-insensitively
+  #[arg(short, long)]
+  ignore_case: bool,
+
+  /// Treat the pattern as a literal string instead of a regular expression
+  #[arg(short = 'F', long)]
+  fixed_strings: bool,
+}
+
+pub fn grep(args: &GrepArgs) -> Result<()> {
+  let pattern = if args.fixed_strings {
+    regex::escape(&args.pattern)
+  } else {
+    args.pattern.clone()
+  };
+
+  let regex = RegexBuilder::new(&pattern)
+    .case_insensitive(args.ignore_case)
+    .build()?;
+
+  for path in find_files(&args.game_dir, &["bin", "hcb"])? {
+    let result = if is_script(&path) {
+      grep_script(&regex, &path)
+    } else {
+      grep_archive(&regex, &path)
+    };
+
+    if let Err(error) = result {
+      warn!("Skipped {}: {}", path.display(), error.root_cause());
+    }
+  }
+
+  Ok(())
+}
+
+fn grep_archive(regex: &Regex, path: &Path) -> Result<()> {
+  let arc = open_archive(path)?;
+
+  for (i, entry) in arc.entries().iter().enumerate() {
+    if regex.is_match(entry.filename()) {
+      println!("{}:#{i}: {}", path.display(), entry.filename());
+    }
+  }
+
+  Ok(())
+}
+
+fn grep_script(regex: &Regex, path: &Path) -> Result<()> {
+  let hcb = open_script(path)?;
+
+  for function in hcb.functions() {
+    for instruction in function.instructions {
+      if let FvpHcbOpcode::PushString(string) = &instruction.opcode
+        && regex.is_match(string)
+      {
+        println!(
+          "{}:{:#010x} ({}): {string}",
+          path.display(),
+          instruction.address,
+          function_name(function.address),
+        );
+      }
+    }
+  }
+
+  Ok(())
+}
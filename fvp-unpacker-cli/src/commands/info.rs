@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+use fvp_unpacker_core::{archive::hzc::FvpHzcHeader, audio::vorbis::FvpVorbisInfo, prelude::*};
+use serde_json::{Value, json};
+
+use crate::utils::{format_duration, open_archive};
+
+#[derive(Args)]
+pub struct InfoArgs {
+  /// Input file path
+  #[arg(short, long)]
+  input: PathBuf,
+
+  /// Print the information as JSON
+  #[arg(long)]
+  json: bool,
+}
+
+/// Information of an entry, read from its headers only.
+enum EntryInfo {
+  Image(FvpHzcHeader),
+  Audio(FvpVorbisInfo),
+  Unknown,
+}
+
+impl EntryInfo {
+  fn parse(data: &[u8]) -> Self {
+    if let Ok(header) = DynamicFvpHzc::parse_header(data) {
+      Self::Image(header)
+    } else if let Ok(info) = FvpVorbisInfo::parse(data) {
+      Self::Audio(info)
+    } else {
+      Self::Unknown
+    }
+  }
+
+  fn format(&self) -> &'static str {
+    match self {
+      Self::Image(_) => "Image",
+      Self::Audio(_) => "Vorbis",
+      Self::Unknown => "Unknown",
+    }
+  }
+
+  fn details(&self) -> String {
+    match self {
+      Self::Image(header) => format!(
+        "{}x{} at ({}, {}), {} images",
+        header.width, header.height, header.offset_x, header.offset_y, header.count
+      ),
+      Self::Audio(info) => {
+        let mut details = format!(
+          "{} Hz, {} channels, {}",
+          info.sample_rate,
+          info.channels,
+          format_duration(info.duration())
+        );
+
+        if let (Some(start), Some(length)) = (info.loop_start(), info.loop_length()) {
+          details += &format!(", loop {start}+{length}");
+        }
+
+        details
+      }
+      Self::Unknown => String::new(),
+    }
+  }
+
+  fn to_json(&self) -> Value {
+    match self {
+      Self::Image(header) => json!({
+        "width": header.width,
+        "height": header.height,
+        "offset": [header.offset_x, header.offset_y],
+        "count": header.count,
+      }),
+      Self::Audio(info) => json!({
+        "channels": info.channels,
+        "sample_rate": info.sample_rate,
+        "samples": info.samples,
+        "duration": info.duration().as_secs_f64(),
+        "loop_start": info.loop_start(),
+        "loop_length": info.loop_length(),
+        "comments": info.comments,
+      }),
+      Self::Unknown => Value::Null,
+    }
+  }
+}
+
+pub fn info(args: &InfoArgs) -> Result<()> {
+  // TODO: handle other formats
+  let arc = open_archive(&args.input)?;
+
+  let infos: Vec<_> = arc
+    .entries()
+    .iter()
+    .map(|entry| (entry.filename(), EntryInfo::parse(entry.data())))
+    .collect();
+
+  if args.json {
+    let infos: Vec<_> = infos
+      .iter()
+      .map(|(filename, info)| {
+        json!({
+          "filename": filename,
+          "format": info.format(),
+          "info": info.to_json(),
+        })
+      })
+      .collect();
+
+    println!("{}", serde_json::to_string_pretty(&infos)?);
+    return Ok(());
+  }
+
+  let mut table = Table::new();
+  table
+    .load_preset(UTF8_FULL_CONDENSED)
+    .set_header(["Filename", "Format", "Details"]);
+
+  for (filename, info) in &infos {
+    table.add_row([
+      filename.to_string(),
+      info.format().to_string(),
+      info.details(),
+    ]);
+  }
+
+  println!("{table}");
+
+  Ok(())
+}
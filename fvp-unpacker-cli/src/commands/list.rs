@@ -1,12 +1,10 @@
-use std::{fs::File, path::PathBuf};
+use std::path::PathBuf;
 
 use anyhow::Result;
 use clap::Args;
 use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
-use fvp_unpacker_core::prelude::*;
-use memmap2::Mmap;
 
-use crate::utils::human_readable_size;
+use crate::utils::{human_readable_size, open_archive};
 
 #[derive(Args)]
 pub struct ListArgs {
@@ -20,12 +18,8 @@ pub struct ListArgs {
 }
 
 pub fn list(args: &ListArgs) -> Result<()> {
-  let input_file = File::open(&args.input)?;
-  // SAFETY: it's not my fault :(
-  let content = unsafe { Mmap::map(&input_file) }?;
-
   // TODO: handle other formats
-  let arc = FvpBin::parse(content)?;
+  let arc = open_archive(&args.input)?;
 
   let mut table = Table::new();
   table
@@ -1,18 +1,51 @@
+mod assets;
+mod bgm;
+mod callgraph;
+mod cg;
+mod decompile;
+mod grep;
+mod info;
 mod list;
+mod pack;
 mod tachie;
 mod unpack;
+mod unpack_game;
+mod verify;
+mod voice;
 
 use anyhow::Result;
 
-use crate::cli::Cli;
+use crate::cli::Command;
+pub use assets::AssetsArgs;
+pub use bgm::BgmArgs;
+pub use callgraph::CallGraphArgs;
+pub use cg::CgArgs;
+pub use decompile::DecompileArgs;
+pub use grep::GrepArgs;
+pub use info::InfoArgs;
 pub use list::ListArgs;
+pub use pack::PackArgs;
 pub use tachie::TachieArgs;
 pub use unpack::UnpackArgs;
+pub use unpack_game::UnpackGameArgs;
+pub use verify::VerifyArgs;
+pub use voice::VoiceArgs;
 
-pub fn run(args: &Cli) -> Result<()> {
-  match args {
-    Cli::Unpack(args) => unpack::unpack(args),
-    Cli::List(args) => list::list(args),
-    Cli::Tachie(args) => tachie::tachie(args),
+pub fn run(command: &Command) -> Result<()> {
+  match command {
+    Command::Unpack(args) => unpack::unpack(args),
+    Command::UnpackGame(args) => unpack_game::unpack_game(args),
+    Command::List(args) => list::list(args),
+    Command::Info(args) => info::info(args),
+    Command::Tachie(args) => tachie::tachie(args),
+    Command::Cg(args) => cg::cg(args),
+    Command::Bgm(args) => bgm::bgm(args),
+    Command::Verify(args) => verify::verify(args),
+    Command::Pack(args) => pack::pack(args),
+    Command::Decompile(args) => decompile::decompile(args),
+    Command::Voice(args) => voice::voice(args),
+    Command::Assets(args) => assets::assets(args),
+    Command::Callgraph(args) => callgraph::callgraph(args),
+    Command::Grep(args) => grep::grep(args),
   }
 }
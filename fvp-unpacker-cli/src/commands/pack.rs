@@ -0,0 +1,109 @@
+use std::{
+  collections::HashMap,
+  fs::{self, File},
+  io::{BufWriter, Write},
+  path::PathBuf,
+};
+
+use anyhow::{Result, bail};
+use clap::Args;
+use fvp_unpacker_core::{audio::format::FvpAudioFormat, prelude::*};
+use tracing::{error, warn};
+
+use crate::utils::{find_files, open_archive};
+
+#[derive(Args)]
+pub struct PackArgs {
+  /// Original archive path, e.g. `voice.bin`
+  #[arg(short, long)]
+  input: PathBuf,
+
+  /// Directory of the replacement Ogg files, named after the entries, e.g. `yuk0001.ogg`
+  #[arg(short, long)]
+  replacements: PathBuf,
+
+  /// Output archive path
+  #[arg(short, long)]
+  output: PathBuf,
+
+  /// Only warn when the codec, channels or sample rate of a replacement differs from the original,
+  /// or can not be validated as the original is not Ogg audio
+  #[arg(long)]
+  allow_mismatch: bool,
+}
+
+pub fn pack(args: &PackArgs) -> Result<()> {
+  // TODO: handle other formats
+  let arc = open_archive(&args.input)?;
+
+  let mut replacements = HashMap::new();
+  for path in find_files(&args.replacements, &["ogg"])? {
+    let Some(name) = path.file_stem().and_then(|x| x.to_str()) else {
+      continue;
+    };
+
+    if arc.entries().iter().any(|entry| entry.filename() == name) {
+      replacements.insert(name.to_string(), path);
+    } else {
+      warn!("Skipped {}, no entry is named {name}", path.display());
+    }
+  }
+
+  let mut failed = 0;
+  let mut packed = FvpBin::default();
+
+  for entry in arc.entries() {
+    let Some(path) = replacements.get(entry.filename()) else {
+      packed.add_entry(FvpBinEntry::new(entry.filename(), entry.data()));
+      continue;
+    };
+
+    let data = fs::read(path)?;
+
+    let mismatches = match (
+      FvpAudioFormat::parse(entry.data()),
+      FvpAudioFormat::parse(&data),
+    ) {
+      (Ok(original), Ok(replacement)) => original.mismatches(&replacement),
+      (Ok(_), Err(error)) => vec![format!("can not read the replacement ({error})")],
+      // nothing to compare with, so the replacement can not be validated
+      (Err(error), _) => vec![format!(
+        "can not validate the replacement, as the original can not be read ({error})"
+      )],
+    };
+
+    for mismatch in &mismatches {
+      if args.allow_mismatch {
+        warn!("{}: {mismatch}", entry.filename());
+      } else {
+        error!("{}: {mismatch}", entry.filename());
+      }
+    }
+    if !mismatches.is_empty() {
+      failed += 1;
+    }
+
+    packed.add_entry(FvpBinEntry::new(entry.filename(), data));
+  }
+
+  if failed > 0 && !args.allow_mismatch {
+    bail!("{failed} replacements do not match the original entries or can not be validated");
+  }
+
+  if let Some(parent) = args.output.parent()
+    && !parent.as_os_str().is_empty()
+  {
+    fs::create_dir_all(parent)?;
+  }
+  let mut writer = BufWriter::new(File::create(&args.output)?);
+  packed.write(&mut writer)?;
+  writer.flush()?;
+
+  println!(
+    "Replaced {} of {} entries",
+    replacements.len(),
+    arc.entries().len()
+  );
+
+  Ok(())
+}
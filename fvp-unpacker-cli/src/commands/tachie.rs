@@ -2,14 +2,30 @@ use std::{
   collections::HashMap,
   fs::{self, File},
   io::BufWriter,
-  path::PathBuf,
+  path::{Path, PathBuf},
 };
 
-use anyhow::{Result, anyhow, bail};
-use clap::Args;
-use fvp_unpacker_core::prelude::*;
-use memmap2::Mmap;
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{Args, ValueEnum};
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+use fvp_unpacker_core::{
+  archive::hzc::{FvpHzc, FvpHzcEntry},
+  image::{
+    animation::FvpAnimation,
+    atlas::FvpAtlas,
+    layered::{FvpLayer, FvpLayeredImage},
+    tachie::FACIAL_EXPRESSION_SUFFIX,
+  },
+  prelude::*,
+};
 use rayon::prelude::*;
+use rgb::Bgra;
+
+use crate::{
+  error::ArgumentError,
+  progress::EntryProgress,
+  utils::{open_archive, parse_layer, write_atlas_json, write_bgra_png},
+};
 
 #[derive(Args)]
 pub struct TachieArgs {
@@ -22,83 +38,520 @@ pub struct TachieArgs {
   output: PathBuf,
 
   /// The filename containing the character's tachie(立ち絵), e.g. `CHR_雪々_喜_着物U`
-  #[arg(short, long)]
-  character: String,
+  #[arg(short, long, required_unless_present_any = ["all", "list"])]
+  character: Option<String>,
+
+  /// Output the tachie of every character with facial expressions in the archive, into a
+  /// subdirectory per character
+  #[arg(long, conflicts_with = "character")]
+  all: bool,
+
+  /// List the tachie of every character in the archive, grouped by character, pose and outfit,
+  /// with the number of facial expressions
+  #[arg(long, conflicts_with_all = ["character", "all"])]
+  list: bool,
+
+  /// Print the list as JSON
+  #[arg(long, requires = "list")]
+  json: bool,
+
+  /// The filename prefix of the tachie when outputting or listing every character
+  #[arg(long, default_value = "CHR_")]
+  prefix: String,
+
+  /// Treat the images as premultiplied by alpha when compositing
+  #[arg(long)]
+  premultiplied: bool,
+
+  /// Crop the transparent borders of the tachie, and record the cropped area in a JSON file next
+  /// to each image
+  #[arg(long)]
+  trim: bool,
+
+  /// Compose the given layers in order instead of the facial expressions, e.g. `CHR_雪々_頬:1`
+  /// selects the second frame of `CHR_雪々_頬`
+  #[arg(short, long = "layer", value_name = "NAME[:FRAME]", value_parser = parse_layer, conflicts_with = "all")]
+  layers: Vec<(String, usize)>,
+
+  /// Output a layered document with the base and every facial expression as separate layers,
+  /// instead of a PNG for each facial expression
+  #[arg(long, value_enum, conflicts_with_all = ["layers", "trim"])]
+  export: Option<Export>,
+
+  /// Pack the base and every facial expression into a texture atlas, with a JSON descriptor in
+  /// the format of TexturePacker
+  #[arg(long, conflicts_with_all = ["layers", "trim", "export"])]
+  atlas: bool,
+
+  /// Output an animation cycling through every facial expression, instead of a PNG for each
+  #[arg(long, value_enum, conflicts_with_all = ["layers", "trim", "export", "atlas"])]
+  animate: Option<Animation>,
+
+  /// How long each frame of the animation is shown in milliseconds
+  #[arg(long, default_value_t = 500, requires = "animate")]
+  delay: u16,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Export {
+  /// OpenRaster, supported by Krita and GIMP
+  Ora,
+  /// Photoshop document
+  Psd,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Animation {
+  /// Animated PNG, keeping the translucency
+  Apng,
+  Gif,
+}
+
+fn alpha(args: &TachieArgs) -> FvpAlpha {
+  if args.premultiplied {
+    FvpAlpha::Premultiplied
+  } else {
+    FvpAlpha::Straight
+  }
 }
 
 pub fn tachie(args: &TachieArgs) -> Result<()> {
+  // TODO: handle other formats
+  let arc = open_archive(&args.input)?;
+
+  if args.list {
+    return tachie_list(args, &arc);
+  }
+
   if !args.output.is_dir() {
     fs::create_dir_all(&args.output)?;
   }
 
-  let input_file = File::open(&args.input)?;
-  // SAFETY: it's not my fault :(
-  let content = unsafe { Mmap::map(&input_file) }?;
-
-  // TODO: handle other formats
-  let arc = FvpBin::parse(content)?;
-
   let entries: HashMap<_, _> = arc
     .entries()
     .iter()
     .map(|entry| (entry.filename(), entry))
     .collect();
 
-  // TODO: multiple characters
+  let Some(character) = &args.character else {
+    return tachie_all(args, &entries);
+  };
+
   let base = *entries
-    .get(args.character.as_str())
-    .ok_or(anyhow!("No such character"))?;
+    .get(character.as_str())
+    .ok_or(ArgumentError(format!("No such character {character}")))?;
+
+  if !args.layers.is_empty() {
+    return tachie_layers(args, &entries, base);
+  }
+
   let facial_expression = *entries
-    .get(format!("{}_表情", args.character).as_str())
+    .get(format!("{character}{FACIAL_EXPRESSION_SUFFIX}").as_str())
     .ok_or(anyhow!("Can not find facial expression"))?;
 
-  drop(entries);
+  render_tachie(args, base, facial_expression, &args.output)
+}
 
-  let DynamicFvpHzc::Bgra(base_hzc) = DynamicFvpHzc::parse(base.data())? else {
-    bail!("The tachie must be BGRA images");
-  };
+fn tachie_list(args: &TachieArgs, arc: &FvpBin) -> Result<()> {
+  let tachies = arc.tachies(&args.prefix);
 
-  let base_image = {
-    let base_entries = base_hzc.entries();
+  if args.json {
+    println!("{}", serde_json::to_string_pretty(&tachies)?);
+    return Ok(());
+  }
+
+  let mut table = Table::new();
+  table.load_preset(UTF8_FULL_CONDENSED).set_header([
+    "Character",
+    "Pose",
+    "Outfit",
+    "Filename",
+    "Size",
+    "Facial expressions",
+  ]);
+
+  for tachie in &tachies {
+    table.add_row([
+      tachie.character.clone(),
+      tachie.pose.clone().unwrap_or_default(),
+      tachie.outfit.clone().unwrap_or_default(),
+      tachie.name.clone(),
+      format!("{}x{}", tachie.width, tachie.height),
+      tachie
+        .facial_expressions
+        .map(|count| count.to_string())
+        .unwrap_or("-".to_string()),
+    ]);
+  }
+
+  println!("{table}");
+
+  let renderable = tachies
+    .iter()
+    .filter(|tachie| tachie.facial_expressions.is_some())
+    .count();
+  println!(
+    "{} tachie, {renderable} with facial expressions",
+    tachies.len()
+  );
+
+  Ok(())
+}
+
+fn tachie_all(args: &TachieArgs, entries: &HashMap<&str, &FvpBinEntry>) -> Result<()> {
+  let mut pairs = Vec::new();
+  let mut missing = Vec::new();
 
-    if base_entries.len() != 1 {
-      bail!("The count of images of the tachie must be 1");
+  for (name, base) in entries {
+    if !name.starts_with(&args.prefix) || name.ends_with(FACIAL_EXPRESSION_SUFFIX) {
+      continue;
     }
 
-    &base_entries[0]
+    match entries.get(format!("{name}{FACIAL_EXPRESSION_SUFFIX}").as_str()) {
+      Some(facial_expression) => pairs.push((*base, *facial_expression)),
+      None => missing.push(*name),
+    }
+  }
+
+  pairs.sort_by_key(|(base, _)| base.filename());
+  missing.sort();
+
+  let total_bytes = pairs
+    .iter()
+    .map(|(base, facial_expression)| (base.data().len() + facial_expression.data().len()) as u64)
+    .sum();
+  let progress = EntryProgress::new(pairs.len(), total_bytes);
+
+  let result = pairs
+    .par_iter()
+    .map(|(base, facial_expression)| {
+      let name = base.filename();
+      let character = name[args.prefix.len()..]
+        .split('_')
+        .next()
+        .filter(|character| !character.is_empty())
+        .unwrap_or(name);
+
+      let output = args.output.join(character);
+      let result = fs::create_dir_all(&output)
+        .map_err(anyhow::Error::from)
+        .and_then(|()| render_tachie(args, base, facial_expression, &output))
+        .with_context(|| format!("Failed to output the tachie of {name}"));
+
+      // Failed sprites are done as well
+      progress.inc(name, base.data().len() + facial_expression.data().len());
+      result
+    })
+    .collect::<Result<()>>();
+  progress.finish();
+  result?;
+
+  println!("Output the tachie of {} sprites", pairs.len());
+
+  if !missing.is_empty() {
+    println!("No facial expressions found for:");
+    for name in missing {
+      println!("  {name}");
+    }
+  }
+
+  Ok(())
+}
+
+fn parse_bgra(entry: &FvpBinEntry) -> Result<FvpHzc<Bgra<u8>>> {
+  let DynamicFvpHzc::Bgra(hzc) = DynamicFvpHzc::parse(entry.data())? else {
+    bail!("{} must be BGRA images", entry.filename());
   };
 
-  let DynamicFvpHzc::Bgra(facial_expression_hzc) = DynamicFvpHzc::parse(facial_expression.data())?
-  else {
-    bail!("The facial expression must be BGRA images");
+  Ok(hzc)
+}
+
+fn parse_base(base: &FvpBinEntry) -> Result<FvpHzcEntry<Bgra<u8>>> {
+  let base_hzc = parse_bgra(base)?;
+  let base_entries = base_hzc.entries();
+
+  if base_entries.len() != 1 {
+    bail!("The count of images of the tachie must be 1");
+  }
+
+  Ok(base_entries[0].clone())
+}
+
+fn tachie_layers(
+  args: &TachieArgs,
+  entries: &HashMap<&str, &FvpBinEntry>,
+  base: &FvpBinEntry,
+) -> Result<()> {
+  let mut compositor = FvpCompositor::new(parse_base(base)?).with_alpha(alpha(args));
+
+  for (name, frame) in &args.layers {
+    let layer = *entries
+      .get(name.as_str())
+      .ok_or(ArgumentError(format!("Can not find layer {name}")))?;
+    let layer_hzc = parse_bgra(layer)?;
+
+    let layer_image = layer_hzc
+      .entries()
+      .get(*frame)
+      .ok_or(ArgumentError(format!(
+        "Layer {name} has only {} images",
+        layer_hzc.entries().len()
+      )))?;
+
+    compositor.add_layer(layer_image);
+  }
+
+  let frames: Vec<_> = args
+    .layers
+    .iter()
+    .map(|(_, frame)| frame.to_string())
+    .collect();
+  let output_path = args
+    .output
+    .join(format!("{}-{}.png", base.filename(), frames.join("-")));
+  write_bgra_png(compositor.image(), &output_path, args.trim)?;
+
+  Ok(())
+}
+
+fn render_tachie(
+  args: &TachieArgs,
+  base: &FvpBinEntry,
+  facial_expression: &FvpBinEntry,
+  output: &Path,
+) -> Result<()> {
+  let name = base.filename();
+
+  let base_image = parse_base(base)?;
+  let facial_expression_hzc = parse_bgra(facial_expression)?;
+  let facial_expressions = facial_expression_hzc.entries();
+
+  // The progress is of the facial expressions, except with `--all` which counts the tachie
+  let progress = (!args.all).then(|| {
+    let total_bytes = facial_expressions
+      .iter()
+      .map(|image| size_of_val(image.data.buf()) as u64)
+      .sum();
+    EntryProgress::new(facial_expressions.len(), total_bytes)
+  });
+  let done = |i: usize| {
+    if let Some(progress) = &progress {
+      let image = &facial_expressions[i];
+      let name = format!("{name}{FACIAL_EXPRESSION_SUFFIX}-{i}");
+      progress.inc(&name, size_of_val(image.data.buf()));
+    }
   };
 
+  let result =
+    render_facial_expressions(args, name, base_image, &facial_expression_hzc, output, done);
+
+  if let Some(progress) = progress {
+    progress.finish();
+  }
+  result
+}
+
+/// Output the facial expressions of a tachie as `args` asks, calling `done` with the index of
+/// each facial expression once it is output.
+fn render_facial_expressions(
+  args: &TachieArgs,
+  name: &str,
+  base_image: FvpHzcEntry<Bgra<u8>>,
+  facial_expression_hzc: &FvpHzc<Bgra<u8>>,
+  output: &Path,
+  done: impl Fn(usize) + Sync,
+) -> Result<()> {
+  let count = facial_expression_hzc.entries().len();
+
+  if args.atlas {
+    let result = atlas_tachie(name, base_image, facial_expression_hzc, output);
+    (0..count).for_each(done);
+    return result;
+  }
+
+  if let Some(export) = args.export {
+    let result = export_tachie(export, name, base_image, facial_expression_hzc, output);
+    (0..count).for_each(done);
+    return result;
+  }
+
+  if let Some(animation) = args.animate {
+    let frames: Vec<_> = facial_expression_hzc
+      .entries()
+      .par_iter()
+      .enumerate()
+      .map(|(i, facial_expression)| {
+        let frame = compose_tachie(&base_image, facial_expression, alpha(args));
+        done(i);
+        frame
+      })
+      .collect();
+
+    let animation_file = |extension| File::create(output.join(format!("{name}.{extension}")));
+    let frames = FvpAnimation::new(frames).with_delay(args.delay);
+
+    match animation {
+      Animation::Apng => frames.write_apng(BufWriter::new(animation_file("png")?))?,
+      Animation::Gif => frames.write_gif(BufWriter::new(animation_file("gif")?))?,
+    }
+
+    return Ok(());
+  }
+
   facial_expression_hzc
     .entries()
     .par_iter()
     .enumerate()
     .map(|(i, facial_expression)| {
-      let mut base = base_image.clone();
-
-      base
-        .data
-        .sub_image_mut(
-          facial_expression.offset.0.into(),
-          facial_expression.offset.1.into(),
-          facial_expression.data.width(),
-          facial_expression.data.height(),
-        )
-        .rows_mut()
-        .zip(facial_expression.data.rows())
-        .for_each(|(dst, src)| dst.copy_from_slice(src));
-
-      let output_path = args.output.join(format!("{}-{i}.png", args.character));
-      let output_file = File::create(output_path)?;
-      base.write_to_png(BufWriter::new(output_file))?;
-
-      Ok(())
+      let image = compose_tachie(&base_image, facial_expression, alpha(args));
+
+      let output_path = output.join(format!("{name}-{i}.png"));
+      let result = write_bgra_png(&image, &output_path, args.trim);
+
+      // Failed facial expressions are done as well
+      done(i);
+      result.map(drop)
     })
     .collect::<Result<()>>()?;
 
   Ok(())
 }
+
+/// Draw a facial expression on the base of a tachie, at its offset relative to the base.
+fn compose_tachie(
+  base_image: &FvpHzcEntry<Bgra<u8>>,
+  facial_expression: &FvpHzcEntry<Bgra<u8>>,
+  alpha: FvpAlpha,
+) -> FvpHzcEntry<Bgra<u8>> {
+  let mut compositor = FvpCompositor::new(base_image.clone()).with_alpha(alpha);
+  compositor.add_layer(facial_expression);
+  compositor.into_image()
+}
+
+/// The base of a tachie with a layer for each facial expression, placed like [`compose_tachie`].
+fn tachie_document(
+  name: &str,
+  base_image: FvpHzcEntry<Bgra<u8>>,
+  facial_expressions: &[FvpHzcEntry<Bgra<u8>>],
+) -> FvpLayeredImage {
+  let (width, height) = (base_image.data.width(), base_image.data.height());
+  let mut document = FvpLayeredImage::new(width, height);
+
+  document.add_layer(FvpLayer {
+    name: name.to_string(),
+    image: base_image,
+    x: 0,
+    y: 0,
+    visible: true,
+  });
+
+  // Only the first facial expression is visible, like the first PNG when not exporting
+  for (i, facial_expression) in facial_expressions.iter().enumerate() {
+    document.add_layer(FvpLayer {
+      name: format!("{name}{FACIAL_EXPRESSION_SUFFIX}-{i}"),
+      image: facial_expression.clone(),
+      x: facial_expression.offset.0.into(),
+      y: facial_expression.offset.1.into(),
+      visible: i == 0,
+    });
+  }
+
+  document
+}
+
+fn export_tachie(
+  export: Export,
+  name: &str,
+  base_image: FvpHzcEntry<Bgra<u8>>,
+  facial_expression_hzc: &FvpHzc<Bgra<u8>>,
+  output: &Path,
+) -> Result<()> {
+  let document = tachie_document(name, base_image, facial_expression_hzc.entries());
+
+  let output_file = |extension| File::create(output.join(format!("{name}.{extension}")));
+
+  match export {
+    Export::Ora => document.write_ora(BufWriter::new(output_file("ora")?))?,
+    Export::Psd => document.write_psd(BufWriter::new(output_file("psd")?))?,
+  }
+
+  Ok(())
+}
+
+fn atlas_tachie(
+  name: &str,
+  mut base_image: FvpHzcEntry<Bgra<u8>>,
+  facial_expression_hzc: &FvpHzc<Bgra<u8>>,
+  output: &Path,
+) -> Result<()> {
+  // The offsets of the facial expressions are relative to the base, like in `compose_tachie`, so
+  // the base is the origin of the frames
+  base_image.offset = (0, 0);
+  let source_size = (base_image.data.width(), base_image.data.height());
+
+  let frames = facial_expression_hzc
+    .entries()
+    .iter()
+    .enumerate()
+    .map(|(i, facial_expression)| {
+      (
+        format!("{name}{FACIAL_EXPRESSION_SUFFIX}-{i}"),
+        facial_expression,
+      )
+    });
+  let atlas = FvpAtlas::pack(
+    std::iter::once((name.to_string(), &base_image)).chain(frames),
+    2,
+  );
+
+  let output_path = output.join(format!("{name}-atlas.png"));
+  atlas
+    .image()
+    .write_to_png(BufWriter::new(File::create(&output_path)?))?;
+  write_atlas_json(&atlas, &output_path, source_size)?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use imgref::ImgVec;
+
+  use super::*;
+
+  const RED: Bgra<u8> = Bgra {
+    b: 0,
+    g: 0,
+    r: 255,
+    a: 255,
+  };
+  const BLUE: Bgra<u8> = Bgra {
+    b: 255,
+    g: 0,
+    r: 0,
+    a: 255,
+  };
+
+  fn solid(size: usize, offset: (u16, u16), pixel: Bgra<u8>) -> FvpHzcEntry<Bgra<u8>> {
+    FvpHzcEntry {
+      offset,
+      data: ImgVec::new(vec![pixel; size * size], size, size),
+    }
+  }
+
+  #[test]
+  fn facial_expressions_relative_to_base() {
+    // The base is positioned on the screen, its facial expressions are not
+    let base = solid(4, (100, 50), RED);
+    let facial_expression = solid(1, (2, 1), BLUE);
+
+    let image = compose_tachie(&base, &facial_expression, FvpAlpha::Straight);
+    assert_eq!(image.data[(2usize, 1usize)], BLUE);
+    assert_eq!(image.data.buf().iter().filter(|x| **x == BLUE).count(), 1);
+
+    let document = tachie_document("CHR", base, &[facial_expression]);
+    let layer = &document.layers()[1];
+    assert_eq!((layer.x, layer.y), (2, 1));
+    assert_eq!(document.merge().data.buf(), image.data.buf());
+  }
+}
@@ -1,14 +1,32 @@
 use std::{
+  borrow::Cow,
+  collections::HashMap,
   fs::{self, File},
   io::BufWriter,
-  path::PathBuf,
+  path::{Path, PathBuf},
+  sync::atomic::{AtomicUsize, Ordering},
+  time::Duration,
 };
 
-use anyhow::Result;
-use clap::Args;
-use fvp_unpacker_core::prelude::*;
-use memmap2::Mmap;
+use anyhow::{Context, Result, bail};
+use clap::{Args, ValueEnum};
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+use fvp_unpacker_core::{
+  archive::{group::name_prefix, hzc::FvpHzc},
+  audio::vorbis::FvpVorbisInfo,
+  image::atlas::FvpAtlas,
+  prelude::*,
+};
 use rayon::prelude::*;
+use regex::Regex;
+use tracing::{debug, warn};
+
+use crate::{
+  cache::UnpackCache,
+  error::ArgumentError,
+  progress::EntryProgress,
+  utils::{AtlasPixel, format_duration, open_archive, write_atlas_json, write_bgra_png},
+};
 
 #[derive(Args)]
 pub struct UnpackArgs {
@@ -19,6 +37,107 @@ pub struct UnpackArgs {
   /// Output directory path
   #[arg(short, long, default_value = "./output")]
   output: PathBuf,
+
+  /// Place every image at its offset on a transparent canvas of `WxH`, or `auto` to fit all images
+  /// in the archive
+  #[arg(long, value_name = "WxH|auto", value_parser = parse_canvas)]
+  canvas: Option<Canvas>,
+
+  /// Crop the transparent borders of the images, and record the cropped area in a JSON file next
+  /// to each image. Only images with an alpha channel are trimmed, the others are unpacked as they
+  /// are with a warning
+  #[arg(long)]
+  trim: bool,
+
+  /// Pack the images of each entry into a texture atlas, with a JSON descriptor in the format of
+  /// TexturePacker
+  #[arg(long, conflicts_with_all = ["canvas", "trim"])]
+  atlas: bool,
+
+  /// Put the entries into a subdirectory per group, e.g. per speaker of the voices
+  #[arg(long, value_enum)]
+  group_by: Option<GroupBy>,
+
+  /// The pattern to group by, the first capture group (or the whole match if none) names the
+  /// group
+  #[arg(long, value_parser = Regex::new, required_if_eq("group_by", "regex"))]
+  group_pattern: Option<Regex>,
+
+  /// A file mapping the groups to readable names for the subdirectories, one per line as
+  /// `GROUP = NAME`
+  #[arg(long, requires = "group_by")]
+  group_names: Option<PathBuf>,
+
+  /// Unpack the other entries when some fail, and list the failures at the end
+  #[arg(short, long)]
+  keep_going: bool,
+
+  /// When to overwrite the files of entries unpacked before. Every run records the hashes and the
+  /// files of the unpacked entries in `.fvp-unpacker-cache.json` in the output directory, which
+  /// the later runs compare with
+  #[arg(long, value_enum, default_value_t = Overwrite::Always)]
+  overwrite: Overwrite,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Overwrite {
+  /// Skip the entries which have been unpacked, i.e. every file written for them by the last run
+  /// still exists, or the first file they would be unpacked to exists
+  Never,
+  /// Unpack every entry again
+  Always,
+  /// Skip the entries whose data is unchanged since the last run, by the hashes cached in the
+  /// output directory
+  IfChanged,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum GroupBy {
+  /// The leading part of the filenames before any digit or `_`, e.g. `yuk` of `yuk0001`
+  Prefix,
+  /// The match of `--group-pattern`
+  Regex,
+}
+
+fn parse_group_names(names: &str) -> Result<HashMap<String, String>> {
+  let mut groups = HashMap::new();
+
+  for (i, line) in names.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    let Some((group, name)) = line.split_once('=') else {
+      bail!(ArgumentError(format!(
+        "Missing `=` at line {} of the group names file",
+        i + 1
+      )));
+    };
+
+    groups.insert(group.trim().to_string(), name.trim().to_string());
+  }
+
+  Ok(groups)
+}
+
+#[derive(Clone, Copy)]
+enum Canvas {
+  Fixed(usize, usize),
+  Auto,
+}
+
+fn parse_canvas(canvas: &str) -> Result<Canvas, String> {
+  if canvas == "auto" {
+    return Ok(Canvas::Auto);
+  }
+
+  canvas
+    .split_once(['x', 'X'])
+    .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+    .filter(|&(width, height)| width > 0 && height > 0)
+    .map(|(width, height)| Canvas::Fixed(width, height))
+    .ok_or(format!("invalid canvas size `{canvas}`"))
 }
 
 pub fn unpack(args: &UnpackArgs) -> Result<()> {
@@ -26,47 +145,502 @@ pub fn unpack(args: &UnpackArgs) -> Result<()> {
     fs::create_dir_all(&args.output)?;
   }
 
-  let input_file = File::open(&args.input)?;
-  // SAFETY: it's not my fault :(
-  let content = unsafe { Mmap::map(&input_file) }?;
-
   // TODO: handle other formats
-  let arc = FvpBin::parse(content)?;
+  let arc = open_archive(&args.input)?;
 
-  arc
+  let canvas_size = match args.canvas {
+    Some(Canvas::Fixed(width, height)) => Some((width, height)),
+    Some(Canvas::Auto) => Some(
+      arc
+        .entries()
+        .iter()
+        .filter_map(|entry| DynamicFvpHzc::parse_header(entry.data()).ok())
+        .fold((0, 0), |(width, height), header| {
+          (
+            width.max(header.offset_x as usize + header.width as usize),
+            height.max(header.offset_y as usize + header.height as usize),
+          )
+        }),
+    ),
+    None => None,
+  };
+
+  let groups = match args.group_by {
+    Some(GroupBy::Prefix) => arc.group_by(|name| name_prefix(name).map(str::to_string)),
+    Some(GroupBy::Regex) => {
+      let Some(pattern) = &args.group_pattern else {
+        bail!(ArgumentError(
+          "`--group-pattern` is required to group by regex".to_string()
+        ));
+      };
+
+      arc.group_by(|name| {
+        let captures = pattern.captures(name)?;
+        let group = captures.get(1).or(captures.get(0))?;
+        Some(group.as_str().to_string())
+      })
+    }
+    None => Default::default(),
+  };
+
+  let group_names = match &args.group_names {
+    Some(path) => {
+      let names =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+      parse_group_names(&names)?
+    }
+    None => HashMap::new(),
+  };
+
+  let mut directories = HashMap::new();
+  for (group, entries) in &groups {
+    let directory = args.output.join(group_names.get(group).unwrap_or(group));
+    fs::create_dir_all(&directory)?;
+
+    for entry in entries {
+      directories.insert(entry.filename(), directory.clone());
+    }
+  }
+
+  let options = EntryOptions {
+    canvas_size,
+    trim: args.trim,
+    atlas: args.atlas,
+  };
+
+  let total_bytes = arc
+    .entries()
+    .iter()
+    .map(|entry| entry.data().len() as u64)
+    .sum();
+  let progress = EntryProgress::new(arc.entries().len(), total_bytes);
+
+  // The grouping changes where the entries are unpacked to, so it is part of the options
+  let mut sorted_group_names: Vec<_> = group_names.iter().collect();
+  sorted_group_names.sort();
+  let fingerprint = format!(
+    "{options:?} {:?} {:?} {sorted_group_names:?}",
+    args.group_by,
+    args.group_pattern.as_ref().map(Regex::as_str),
+  );
+  let cache = match args.overwrite {
+    Overwrite::Always => UnpackCache::default(),
+    Overwrite::Never | Overwrite::IfChanged => UnpackCache::load(&args.output, &fingerprint),
+  };
+  let hashes: HashMap<_, _> = arc
     .entries()
     .par_iter()
-    .map(|entry| {
-      let filename = entry.filename();
-
-      // TODO: handle other file formats
-      match DynamicFvpHzc::parse(entry.data())? {
-        DynamicFvpHzc::Bgr(hzc) => {
-          for (i, img) in hzc.entries().iter().enumerate() {
-            let output_path = args.output.join(format!("{filename}-{i}.png"));
-            let output_file = File::create(output_path)?;
-            img.write_to_png(BufWriter::new(output_file))?;
-          }
-        }
-        DynamicFvpHzc::Bgra(hzc) => {
-          for (i, img) in hzc.entries().iter().enumerate() {
-            let output_path = args.output.join(format!("{filename}-{i}.png"));
-            let output_file = File::create(output_path)?;
-            img.write_to_png(BufWriter::new(output_file))?;
-          }
-        }
-        DynamicFvpHzc::Gray(hzc) => {
-          for (i, img) in hzc.entries().iter().enumerate() {
-            let output_path = args.output.join(format!("{filename}-{i}.png"));
-            let output_file = File::create(output_path)?;
-            img.write_to_png(BufWriter::new(output_file))?;
-          }
-        }
+    .map(|entry| (entry.filename(), UnpackCache::hash(entry.data())))
+    .collect();
+
+  let skipped = AtomicUsize::new(0);
+  let unpack = |entry: &FvpBinEntry| {
+    let filename = entry.filename();
+    let output = directories.get(filename).unwrap_or(&args.output);
+
+    let target = entry_target(entry, output, &options);
+    let skipped_files = skipped_files(
+      args.overwrite,
+      &cache,
+      &args.output,
+      filename,
+      &hashes[filename],
+      target.as_deref(),
+    );
+
+    let result = if let Some(files) = skipped_files {
+      debug!("Skipped {filename}, which has been unpacked");
+      skipped.fetch_add(1, Ordering::Relaxed);
+      Ok(files)
+    } else {
+      unpack_entry(entry, output, &options).map(|files| {
+        files
+          .iter()
+          .map(|file| relative_path(file, &args.output))
+          .collect()
+      })
+    };
+
+    progress.inc(filename, entry.data().len());
+    result
+  };
+
+  let mut unpacked = Vec::new();
+  let mut failures = Vec::new();
+  if args.keep_going {
+    let results: Vec<_> = arc
+      .entries()
+      .par_iter()
+      .map(|entry| (entry, unpack(entry)))
+      .collect();
+    progress.finish();
+
+    for (entry, result) in results {
+      match result {
+        Ok(files) => unpacked.push((entry, files)),
+        Err(error) => failures.push((entry, error)),
       }
+    }
+  } else {
+    let result = arc
+      .entries()
+      .par_iter()
+      .map(|entry| {
+        let files = unpack(entry).with_context(|| {
+          format!(
+            "Failed to unpack {} from {}",
+            entry.filename(),
+            args.input.display()
+          )
+        })?;
+        Ok((entry, files))
+      })
+      .collect::<Result<Vec<_>>>();
+    progress.finish();
+    unpacked = result?;
+  }
+
+  let mut new_cache = UnpackCache::new(fingerprint);
+  for (entry, files) in unpacked {
+    let filename = entry.filename();
+    new_cache.insert(filename, &hashes[filename], files);
+  }
+  new_cache.save(&args.output)?;
+
+  let skipped = skipped.into_inner();
+  if skipped > 0 {
+    println!("Skipped {skipped} entries which have been unpacked");
+  }
+
+  if !groups.is_empty() {
+    let mut table = Table::new();
+    table
+      .load_preset(UTF8_FULL_CONDENSED)
+      .set_header(["Group", "Name", "Entries", "Duration"]);
+
+    for (group, entries) in &groups {
+      let duration: Duration = entries
+        .iter()
+        .filter_map(|entry| FvpVorbisInfo::parse(entry.data()).ok())
+        .map(|info| info.duration())
+        .sum();
 
-      Ok(())
-    })
-    .collect::<Result<()>>()?;
+      table.add_row([
+        group.clone(),
+        group_names.get(group).cloned().unwrap_or_default(),
+        entries.len().to_string(),
+        format_duration(duration),
+      ]);
+    }
+
+    println!("{table}");
+
+    let ungrouped = arc.entries().len() - directories.len();
+    if ungrouped > 0 {
+      println!("{ungrouped} entries are not in any group");
+    }
+  }
+
+  if !failures.is_empty() {
+    failures.sort_by_key(|(entry, _)| entry.offset());
+
+    for (entry, error) in &failures {
+      let offset = entry.offset().unwrap_or_default();
+      eprintln!("Failed {} at {offset:#010x}: {error:#}", entry.filename());
+    }
+
+    bail!(
+      "{} of {} entries failed to unpack",
+      failures.len(),
+      arc.entries().len()
+    );
+  }
 
   Ok(())
 }
+
+/// The files an entry unpacked before is skipped with, relative to `dir`, or `None` to unpack it.
+/// `target` is the first file the entry would be unpacked to.
+fn skipped_files(
+  overwrite: Overwrite,
+  cache: &UnpackCache,
+  dir: &Path,
+  filename: &str,
+  hash: &str,
+  target: Option<&Path>,
+) -> Option<Vec<PathBuf>> {
+  match overwrite {
+    Overwrite::Never if cache.is_unpacked(dir, filename) => Some(cache.files(filename).to_vec()),
+    // Unpacked without the cache, e.g. by another tool or with other options
+    Overwrite::Never => target
+      .filter(|target| target.exists())
+      .map(|target| vec![relative_path(target, dir)]),
+    Overwrite::Always => None,
+    Overwrite::IfChanged if cache.is_unchanged(dir, filename, hash) => {
+      Some(cache.files(filename).to_vec())
+    }
+    Overwrite::IfChanged => None,
+  }
+}
+
+/// The cache records the files relative to the output directory.
+fn relative_path(file: &Path, dir: &Path) -> PathBuf {
+  file.strip_prefix(dir).unwrap_or(file).to_path_buf()
+}
+
+/// The first file [`unpack_entry`] writes for an entry, if any. Only the header is parsed.
+fn entry_target(entry: &FvpBinEntry, output: &Path, options: &EntryOptions) -> Option<PathBuf> {
+  let filename = entry.filename();
+
+  if !entry.data().starts_with(b"hzc1") {
+    return Some(if entry.data().starts_with(b"OggS") {
+      output.join(format!("{filename}.ogg"))
+    } else {
+      output.join(filename)
+    });
+  }
+
+  let header = DynamicFvpHzc::parse_header(entry.data()).ok()?;
+  if options.atlas {
+    Some(output.join(format!("{filename}-atlas.png")))
+  } else if header.count > 0 {
+    Some(output.join(format!("{filename}-0.png")))
+  } else {
+    None
+  }
+}
+
+/// How each entry is unpacked, besides where to.
+#[derive(Debug, Default)]
+pub(super) struct EntryOptions {
+  pub canvas_size: Option<(usize, usize)>,
+  pub trim: bool,
+  pub atlas: bool,
+}
+
+/// Unpack an entry into `output`, as PNG images if it is an image, otherwise as is. Returns the
+/// written files.
+pub(super) fn unpack_entry(
+  entry: &FvpBinEntry,
+  output: &Path,
+  options: &EntryOptions,
+) -> Result<Vec<PathBuf>> {
+  let filename = entry.filename();
+
+  if !entry.data().starts_with(b"hzc1") {
+    // TODO: handle other file formats
+    let output_path = if entry.data().starts_with(b"OggS") {
+      output.join(format!("{filename}.ogg"))
+    } else {
+      output.join(filename)
+    };
+    fs::write(&output_path, entry.data())?;
+    return Ok(vec![output_path]);
+  }
+
+  let mut files = Vec::new();
+
+  match DynamicFvpHzc::parse(entry.data())? {
+    DynamicFvpHzc::Bgr(hzc) => {
+      if options.atlas {
+        return write_atlas(output, filename, &hzc);
+      }
+      if options.trim {
+        warn!("Did not trim {filename}, which has no alpha channel");
+      }
+
+      for (i, img) in hzc.entries().iter().enumerate() {
+        let img = match options.canvas_size {
+          Some((width, height)) => Cow::Owned(img.place_on_canvas(width, height)),
+          None => Cow::Borrowed(img),
+        };
+
+        let output_path = output.join(format!("{filename}-{i}.png"));
+        let output_file = File::create(&output_path)?;
+        img.write_to_png(BufWriter::new(output_file))?;
+        files.push(output_path);
+      }
+    }
+    DynamicFvpHzc::Bgra(hzc) => {
+      if options.atlas {
+        return write_atlas(output, filename, &hzc);
+      }
+
+      for (i, img) in hzc.entries().iter().enumerate() {
+        let img = match options.canvas_size {
+          Some((width, height)) => Cow::Owned(img.place_on_canvas(width, height)),
+          None => Cow::Borrowed(img),
+        };
+
+        let output_path = output.join(format!("{filename}-{i}.png"));
+        files.extend(write_bgra_png(&img, &output_path, options.trim)?);
+      }
+    }
+    DynamicFvpHzc::Gray(hzc) => {
+      if options.atlas {
+        return write_atlas(output, filename, &hzc);
+      }
+      if options.trim {
+        warn!("Did not trim {filename}, which has no alpha channel");
+      }
+
+      for (i, img) in hzc.entries().iter().enumerate() {
+        let img = match options.canvas_size {
+          Some((width, height)) => Cow::Owned(img.place_on_canvas(width, height)),
+          None => Cow::Borrowed(img),
+        };
+
+        let output_path = output.join(format!("{filename}-{i}.png"));
+        let output_file = File::create(&output_path)?;
+        img.write_to_png(BufWriter::new(output_file))?;
+        files.push(output_path);
+      }
+    }
+  }
+
+  Ok(files)
+}
+
+/// Pack the images of an entry, which are named as if they were unpacked separately, and write the
+/// atlas with its JSON descriptor. The source size is the smallest canvas which every image fits in
+/// at its offset. Returns the written files.
+fn write_atlas<Pixel: AtlasPixel>(
+  output: &Path,
+  filename: &str,
+  hzc: &FvpHzc<Pixel>,
+) -> Result<Vec<PathBuf>> {
+  let source_size = hzc
+    .entries()
+    .iter()
+    .map(|img| img.canvas_size())
+    .fold((0, 0), |(width, height), (w, h)| {
+      (width.max(w), height.max(h))
+    });
+
+  let atlas = FvpAtlas::pack(
+    hzc
+      .entries()
+      .iter()
+      .enumerate()
+      .map(|(i, img)| (format!("{filename}-{i}"), img)),
+    2,
+  );
+
+  let output_path = output.join(format!("{filename}-atlas.png"));
+  Pixel::write_png(atlas.image(), &output_path)?;
+  let json_path = write_atlas_json(&atlas, &output_path, source_size)?;
+
+  Ok(vec![output_path, json_path])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// An empty directory for a test.
+  fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fvp-unpacker-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn target_of_entries() {
+    let options = EntryOptions::default();
+    let output = Path::new("output");
+
+    let ogg = FvpBinEntry::new("bgm01", b"OggS".to_vec());
+    assert_eq!(
+      entry_target(&ogg, output, &options),
+      Some(output.join("bgm01.ogg"))
+    );
+
+    let raw = FvpBinEntry::new("data.txt", b"text".to_vec());
+    assert_eq!(
+      entry_target(&raw, output, &options),
+      Some(output.join("data.txt"))
+    );
+  }
+
+  #[test]
+  fn keep_going_past_broken_entries() {
+    let dir = test_dir("keep-going");
+    let mut arc = FvpBin::default();
+    arc
+      .add_entry(FvpBinEntry::new("readme.txt", b"hello".to_vec()))
+      .add_entry(FvpBinEntry::new("BG01", b"hzc1broken".to_vec()))
+      .add_entry(FvpBinEntry::new("bgm01", b"OggS".to_vec()));
+    arc
+      .write(File::create(dir.join("data.bin")).unwrap())
+      .unwrap();
+
+    let args = |output: &str, keep_going| UnpackArgs {
+      input: dir.join("data.bin"),
+      output: dir.join(output),
+      canvas: None,
+      trim: false,
+      atlas: false,
+      group_by: None,
+      group_pattern: None,
+      group_names: None,
+      keep_going,
+      overwrite: Overwrite::Always,
+    };
+
+    let error = unpack(&args("kept", true)).unwrap_err();
+    assert_eq!(error.to_string(), "1 of 3 entries failed to unpack");
+    assert_eq!(fs::read(dir.join("kept/readme.txt")).unwrap(), b"hello");
+    assert_eq!(fs::read(dir.join("kept/bgm01.ogg")).unwrap(), b"OggS");
+
+    let error = unpack(&args("stopped", false)).unwrap_err();
+    assert!(error.to_string().starts_with("Failed to unpack BG01"));
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn skip_unpacked_entries() {
+    let dir = test_dir("skip");
+    let target = dir.join("bg01-0.png");
+    fs::write(&target, b"").unwrap();
+    let hash = UnpackCache::hash(b"bg01");
+
+    // Without the cache, only `never` skips the entries whose files exist
+    let cache = UnpackCache::default();
+    let skip = |overwrite, cache: &UnpackCache, target: &Path| {
+      skipped_files(overwrite, cache, &dir, "bg01", &hash, Some(target))
+    };
+    assert_eq!(
+      skip(Overwrite::Never, &cache, &target),
+      Some(vec![PathBuf::from("bg01-0.png")])
+    );
+    assert_eq!(
+      skip(Overwrite::Never, &cache, &dir.join("bg02-0.png")),
+      None
+    );
+    assert_eq!(skip(Overwrite::Always, &cache, &target), None);
+    assert_eq!(skip(Overwrite::IfChanged, &cache, &target), None);
+
+    let mut cache = UnpackCache::new("");
+    cache.insert("bg01", &hash, vec![PathBuf::from("bg01-0.png")]);
+    assert_eq!(
+      skip(Overwrite::IfChanged, &cache, &target),
+      Some(vec![PathBuf::from("bg01-0.png")])
+    );
+    assert_eq!(skip(Overwrite::Always, &cache, &target), None);
+    assert_eq!(
+      skipped_files(
+        Overwrite::IfChanged,
+        &cache,
+        &dir,
+        "bg01",
+        &UnpackCache::hash(b"changed"),
+        Some(&target)
+      ),
+      None
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
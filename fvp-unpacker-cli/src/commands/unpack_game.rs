@@ -0,0 +1,263 @@
+use std::{
+  fs::{self, File},
+  io::{BufWriter, Write},
+  path::{Path, PathBuf},
+};
+
+use anyhow::{Result, bail};
+use clap::Args;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+use rayon::prelude::*;
+use serde_json::json;
+
+use super::unpack::{EntryOptions, unpack_entry};
+use crate::utils::{find_files, human_readable_size, is_script, open_archive, open_script};
+
+#[derive(Args)]
+pub struct UnpackGameArgs {
+  /// The game directory, with the archives(.bin) and the script(.hcb)
+  game_dir: PathBuf,
+
+  /// Output directory path, each archive is unpacked into a subdirectory named after it
+  #[arg(short, long, default_value = "./output")]
+  output: PathBuf,
+}
+
+/// What was unpacked from a file of the game.
+#[derive(Default)]
+struct Report {
+  entries: usize,
+  images: usize,
+  audio: usize,
+  input_size: u64,
+  output_size: u64,
+  /// `(entry, error)`, or an empty entry if the whole file failed
+  failures: Vec<(String, String)>,
+}
+
+pub fn unpack_game(args: &UnpackGameArgs) -> Result<()> {
+  let files = find_files(&args.game_dir, &["bin", "hcb"])?;
+
+  let reports: Vec<_> = files
+    .par_iter()
+    .map(|path| {
+      let relative = path.strip_prefix(&args.game_dir).unwrap_or(path);
+      let mut report = Report::default();
+      let result = if is_script(path) {
+        let output_path = args.output.join(relative).with_extension("txt");
+        decompile_script(path, &output_path, &mut report)
+      } else {
+        let output = args.output.join(relative.with_extension(""));
+        unpack_archive(path, &output, &mut report)
+      };
+
+      if let Err(error) = result {
+        report.failures.push((String::new(), format!("{error:#}")));
+      }
+
+      (relative, report)
+    })
+    .collect();
+
+  let mut table = Table::new();
+  table.load_preset(UTF8_FULL_CONDENSED).set_header([
+    "File", "Entries", "Images", "Audio", "Input", "Output", "Failed",
+  ]);
+
+  for (relative, report) in &reports {
+    table.add_row([
+      relative.display().to_string(),
+      report.entries.to_string(),
+      report.images.to_string(),
+      report.audio.to_string(),
+      human_readable_size(report.input_size as usize),
+      human_readable_size(report.output_size as usize),
+      report.failures.len().to_string(),
+    ]);
+  }
+
+  println!("{table}");
+
+  for (relative, report) in &reports {
+    for (entry, error) in &report.failures {
+      if entry.is_empty() {
+        eprintln!("Failed {}: {error}", relative.display());
+      } else {
+        eprintln!("Failed {}:{entry}: {error}", relative.display());
+      }
+    }
+  }
+
+  let report_json: Vec<_> = reports
+    .iter()
+    .map(|(relative, report)| {
+      json!({
+        "file": relative,
+        "entries": report.entries,
+        "images": report.images,
+        "audio": report.audio,
+        "input_size": report.input_size,
+        "output_size": report.output_size,
+        "failures": report
+          .failures
+          .iter()
+          .map(|(entry, error)| json!({ "entry": entry, "error": error }))
+          .collect::<Vec<_>>(),
+      })
+    })
+    .collect();
+
+  fs::create_dir_all(&args.output)?;
+  let report_path = args.output.join("report.json");
+  let mut writer = BufWriter::new(File::create(&report_path)?);
+  serde_json::to_writer_pretty(&mut writer, &report_json)?;
+  writer.flush()?;
+
+  println!("Wrote the report to {}", report_path.display());
+
+  let failed: usize = reports
+    .iter()
+    .map(|(_, report)| report.failures.len())
+    .sum();
+  if failed > 0 {
+    bail!("{failed} files or entries failed to unpack");
+  }
+
+  Ok(())
+}
+
+fn unpack_archive(path: &Path, output: &Path, report: &mut Report) -> Result<()> {
+  report.input_size = fs::metadata(path)?.len();
+  let arc = open_archive(path)?;
+  fs::create_dir_all(output)?;
+
+  let options = EntryOptions::default();
+  report.entries = arc.entries().len();
+
+  let results: Vec<_> = arc
+    .entries()
+    .par_iter()
+    .map(|entry| unpack_entry(entry, output, &options))
+    .collect();
+
+  for (entry, result) in arc.entries().iter().zip(results) {
+    if entry.data().starts_with(b"hzc1") {
+      report.images += 1;
+    } else if entry.data().starts_with(b"OggS") {
+      report.audio += 1;
+    }
+
+    // The size of the written files, which fails the entry if they can not be read back
+    let result = result.and_then(|files| {
+      files
+        .iter()
+        .map(|file| Ok(fs::metadata(file)?.len()))
+        .sum::<Result<u64>>()
+    });
+
+    match result {
+      Ok(size) => report.output_size += size,
+      Err(error) => {
+        report
+          .failures
+          .push((entry.filename().to_string(), format!("{error:#}")));
+      }
+    }
+  }
+
+  Ok(())
+}
+
+fn decompile_script(path: &Path, output_path: &Path, report: &mut Report) -> Result<()> {
+  report.input_size = fs::metadata(path)?.len();
+  let hcb = open_script(path)?;
+  report.entries = hcb.functions().len();
+
+  if let Some(parent) = output_path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  let script = hcb.decompile().to_string();
+  fs::write(output_path, &script)?;
+  report.output_size = script.len() as u64;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use fvp_unpacker_core::prelude::*;
+  use serde_json::Value;
+
+  use super::*;
+
+  /// An empty directory for a test.
+  fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fvp-unpacker-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  /// A script with a single function which returns at once.
+  fn build_hcb() -> Vec<u8> {
+    let code = [0x01, 0x00, 0x00, 0x04];
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(code.len() as u32 + 4).to_le_bytes());
+    bytes.extend_from_slice(&code);
+    // entry point, non-volatile globals, volatile globals and game mode
+    bytes.extend_from_slice(&4u32.to_le_bytes());
+    bytes.extend_from_slice(&[0; 6]);
+    bytes.push(5);
+    bytes.extend_from_slice(b"test\0");
+    // no syscalls
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    bytes
+  }
+
+  #[test]
+  fn report_unpacked_game() {
+    let dir = test_dir("game");
+    let game_dir = dir.join("game");
+    fs::create_dir_all(&game_dir).unwrap();
+
+    let mut arc = FvpBin::default();
+    arc.add_entry(FvpBinEntry::new("readme.txt", b"hello".to_vec()));
+    arc
+      .write(File::create(game_dir.join("data.bin")).unwrap())
+      .unwrap();
+    fs::write(game_dir.join("SCRIPT.HCB"), build_hcb()).unwrap();
+
+    let args = UnpackGameArgs {
+      game_dir: game_dir.clone(),
+      output: dir.join("output"),
+    };
+    unpack_game(&args).unwrap();
+
+    assert_eq!(
+      fs::read(dir.join("output/data/readme.txt")).unwrap(),
+      b"hello"
+    );
+    let script = fs::read_to_string(dir.join("output/SCRIPT.txt")).unwrap();
+
+    let report: Vec<Value> =
+      serde_json::from_slice(&fs::read(dir.join("output/report.json")).unwrap()).unwrap();
+    let file = |name: &str| report.iter().find(|x| x["file"] == name).unwrap();
+
+    let data = file("data.bin");
+    assert_eq!(data["entries"], 1);
+    assert_eq!(data["images"], 0);
+    assert_eq!(
+      data["input_size"],
+      fs::metadata(game_dir.join("data.bin")).unwrap().len()
+    );
+    assert_eq!(data["output_size"], 5);
+    assert_eq!(data["failures"], Value::Array(Vec::new()));
+
+    let script_report = file("SCRIPT.HCB");
+    assert_eq!(script_report["entries"], 1);
+    assert_eq!(script_report["output_size"], script.len());
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use clap::Args;
+use fvp_unpacker_core::audio::{
+  format::{FvpAudioCodec, FvpAudioFormat},
+  verify::verify_ogg,
+};
+use rayon::prelude::*;
+
+use crate::utils::open_archive;
+
+#[derive(Args)]
+pub struct VerifyArgs {
+  /// Input file path, e.g. `voice.bin`
+  #[arg(short, long)]
+  input: PathBuf,
+
+  /// Only check the pages, without decoding the audio. Opus entries are never decoded
+  #[arg(long)]
+  no_decode: bool,
+}
+
+pub fn verify(args: &VerifyArgs) -> Result<()> {
+  // TODO: handle other formats
+  let arc = open_archive(&args.input)?;
+
+  let entries: Vec<_> = arc
+    .entries()
+    .iter()
+    .filter(|entry| entry.data().starts_with(b"OggS"))
+    .collect();
+
+  let mut failures: Vec<_> = entries
+    .par_iter()
+    .map(|entry| (entry.filename(), verify_ogg(entry.data(), !args.no_decode)))
+    .filter(|(_, issues)| !issues.is_empty())
+    .collect();
+  failures.sort_by_key(|(filename, _)| *filename);
+
+  for (filename, issues) in &failures {
+    for issue in issues {
+      println!("{filename}: {issue}");
+    }
+  }
+
+  println!(
+    "Verified {} Ogg entries, {} failed",
+    entries.len(),
+    failures.len()
+  );
+
+  if !args.no_decode {
+    let opus = entries
+      .iter()
+      .filter_map(|entry| FvpAudioFormat::parse(entry.data()).ok())
+      .filter(|format| format.codec == FvpAudioCodec::Opus)
+      .count();
+
+    if opus > 0 {
+      println!("Only checked the pages of {opus} Opus entries, which can not be decoded");
+    }
+  }
+
+  if !failures.is_empty() {
+    bail!("{} entries failed the verification", failures.len());
+  }
+
+  Ok(())
+}
@@ -0,0 +1,110 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use anyhow::Result;
+use clap::Args;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+use fvp_unpacker_core::script::{decompile::function_name, voice::FvpVoiceSyscalls};
+
+use crate::utils::{csv_row, open_archive, open_script};
+
+#[derive(Args)]
+pub struct VoiceArgs {
+  /// Input script(.hcb) file path
+  #[arg(short, long)]
+  input: PathBuf,
+
+  /// The voice archive, e.g. `voice.bin`, to look the voice entries up in
+  #[arg(long)]
+  voice_archive: Option<PathBuf>,
+
+  /// Syscalls which load voice files [default: AudioLoad]
+  #[arg(long = "voice-syscall")]
+  voice_syscalls: Vec<String>,
+
+  /// Syscalls which print text lines [default: TextPrint]
+  #[arg(long = "text-syscall")]
+  text_syscalls: Vec<String>,
+
+  /// Syscalls which set the speaker name
+  #[arg(long = "speaker-syscall")]
+  speaker_syscalls: Vec<String>,
+
+  /// Print CSV instead of a table
+  #[arg(long)]
+  csv: bool,
+}
+
+pub fn voice(args: &VoiceArgs) -> Result<()> {
+  let hcb = open_script(&args.input)?;
+
+  let voice_entries = match &args.voice_archive {
+    Some(voice_archive) => {
+      let arc = open_archive(voice_archive)?;
+
+      Some(
+        arc
+          .entries()
+          .iter()
+          .map(|entry| entry.filename().to_string())
+          .collect::<HashSet<_>>(),
+      )
+    }
+    None => None,
+  };
+
+  let mut syscalls = FvpVoiceSyscalls::default();
+  if !args.voice_syscalls.is_empty() {
+    syscalls.voice = args.voice_syscalls.clone();
+  }
+  if !args.text_syscalls.is_empty() {
+    syscalls.text = args.text_syscalls.clone();
+  }
+  syscalls.speaker = args.speaker_syscalls.clone();
+
+  let lines = hcb.decompile().voice_lines(&syscalls);
+
+  let mut header = vec!["Function", "Speaker", "Text", "Voice"];
+  if voice_entries.is_some() {
+    header.push("In archive");
+  }
+
+  let rows = lines.iter().map(|line| {
+    let mut row = vec![
+      function_name(line.function),
+      line.speaker.clone().unwrap_or_default(),
+      line.text.clone(),
+      line.voice_entry_name().unwrap_or_default().to_string(),
+    ];
+
+    if let Some(voice_entries) = &voice_entries {
+      row.push(
+        match line.voice_entry_name() {
+          Some(name) if voice_entries.contains(name) => "yes",
+          Some(_) => "no",
+          None => "",
+        }
+        .to_string(),
+      );
+    }
+
+    row
+  });
+
+  if args.csv {
+    println!("{}", csv_row(&header));
+    for row in rows {
+      println!("{}", csv_row(&row));
+    }
+  } else {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED).set_header(header);
+
+    for row in rows {
+      table.add_row(row);
+    }
+
+    println!("{table}");
+  }
+
+  Ok(())
+}
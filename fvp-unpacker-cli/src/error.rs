@@ -0,0 +1,213 @@
+use std::{fmt, io};
+
+use clap::ValueEnum;
+use fvp_unpacker_core::error::FvpError;
+use serde_json::json;
+use zip::result::ZipError;
+
+/// An invalid argument which is only found after parsing the command line, e.g. a malformed
+/// mapping file or an entry which does not exist.
+#[derive(Debug)]
+pub struct ArgumentError(pub String);
+
+impl fmt::Display for ArgumentError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl std::error::Error for ArgumentError {}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ErrorFormat {
+  #[default]
+  Text,
+  Json,
+}
+
+/// The class of an error, which decides the exit code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorClass {
+  Other,
+  Arguments,
+  Io,
+  Format,
+  Decode,
+  Encode,
+}
+
+impl ErrorClass {
+  /// Classify by the first error in the chain with a known class.
+  pub fn of(error: &anyhow::Error) -> Self {
+    error
+      .chain()
+      .find_map(|cause| {
+        if cause.is::<ArgumentError>() || cause.is::<regex::Error>() {
+          Some(Self::Arguments)
+        } else if cause.is::<io::Error>() {
+          Some(Self::Io)
+        } else {
+          cause.downcast_ref::<FvpError>().map(Self::of_fvp)
+        }
+      })
+      .unwrap_or(Self::Other)
+  }
+
+  fn of_fvp(error: &FvpError) -> Self {
+    match error {
+      FvpError::Io(_)
+      | FvpError::ImageEncoding(png::EncodingError::IoError(_))
+      | FvpError::GifEncoding(gif::EncodingError::Io(_))
+      | FvpError::Zip(ZipError::Io(_)) => Self::Io,
+      FvpError::OffsetTooLarge
+      | FvpError::CannotDecodeString
+      | FvpError::StringEncodingMismatch
+      | FvpError::FormatMismatch { .. }
+      | FvpError::InvalidVorbisHeader(_)
+      | FvpError::UnknownAudioCodec
+      | FvpError::UnknownOpcode { .. }
+      | FvpError::ImageWidthMismatch { .. }
+      | FvpError::ImageHeightMismatch { .. }
+      | FvpError::ImageOffsetMismatch { .. } => Self::Format,
+      FvpError::DecompressLengthMismatch { .. } | FvpError::VorbisDecoding(_) => Self::Decode,
+      FvpError::CannotEncodeString
+      | FvpError::ImageEncoding(_)
+      | FvpError::GifEncoding(_)
+      | FvpError::Zip(_) => Self::Encode,
+    }
+  }
+
+  /// The exit code, `2` for bad arguments is the same as clap uses for invalid command lines.
+  pub fn exit_code(self) -> i32 {
+    match self {
+      Self::Other => 1,
+      Self::Arguments => 2,
+      Self::Io => 3,
+      Self::Format => 4,
+      Self::Decode => 5,
+      Self::Encode => 6,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::Other => "other",
+      Self::Arguments => "arguments",
+      Self::Io => "io",
+      Self::Format => "format",
+      Self::Decode => "decode",
+      Self::Encode => "encode",
+    }
+  }
+}
+
+/// Print the error with its causes to stderr, and return the exit code.
+pub fn report(error: &anyhow::Error, format: ErrorFormat) -> i32 {
+  let class = ErrorClass::of(error);
+
+  match format {
+    ErrorFormat::Text => {
+      eprintln!("Error: {error}");
+      for cause in error.chain().skip(1) {
+        eprintln!("  Caused by: {cause}");
+      }
+    }
+    ErrorFormat::Json => {
+      let causes: Vec<_> = error.chain().skip(1).map(|x| x.to_string()).collect();
+      let report = json!({
+        "error": error.to_string(),
+        "causes": causes,
+        "class": class.name(),
+        "exit_code": class.exit_code(),
+      });
+      eprintln!("{report}");
+    }
+  }
+
+  class.exit_code()
+}
+
+#[cfg(test)]
+mod tests {
+  use anyhow::{Context, anyhow};
+
+  use super::*;
+
+  fn class_of(error: impl Into<anyhow::Error>) -> ErrorClass {
+    ErrorClass::of(&error.into())
+  }
+
+  #[test]
+  fn classify_errors() {
+    assert_eq!(class_of(anyhow!("something failed")), ErrorClass::Other);
+
+    assert_eq!(
+      class_of(ArgumentError("no such entry".to_string())),
+      ErrorClass::Arguments
+    );
+    assert_eq!(
+      class_of(regex::Error::Syntax("unclosed group".to_string())),
+      ErrorClass::Arguments
+    );
+
+    let not_found = || io::Error::from(io::ErrorKind::NotFound);
+    assert_eq!(class_of(not_found()), ErrorClass::Io);
+    assert_eq!(class_of(FvpError::Io(not_found())), ErrorClass::Io);
+    assert_eq!(
+      class_of(FvpError::ImageEncoding(png::EncodingError::IoError(
+        not_found()
+      ))),
+      ErrorClass::Io
+    );
+    assert_eq!(
+      class_of(FvpError::GifEncoding(gif::EncodingError::Io(not_found()))),
+      ErrorClass::Io
+    );
+    assert_eq!(
+      class_of(FvpError::Zip(ZipError::Io(not_found()))),
+      ErrorClass::Io
+    );
+
+    assert_eq!(class_of(FvpError::OffsetTooLarge), ErrorClass::Format);
+    assert_eq!(
+      class_of(FvpError::ImageWidthMismatch {
+        expected: 1,
+        found: 2,
+      }),
+      ErrorClass::Format
+    );
+
+    assert_eq!(
+      class_of(FvpError::DecompressLengthMismatch {
+        expected: 1,
+        found: 2,
+      }),
+      ErrorClass::Decode
+    );
+
+    assert_eq!(class_of(FvpError::CannotEncodeString), ErrorClass::Encode);
+    assert_eq!(
+      class_of(FvpError::ImageEncoding(png::EncodingError::LimitsExceeded)),
+      ErrorClass::Encode
+    );
+    assert_eq!(
+      class_of(FvpError::GifEncoding(gif::EncodingError::OutOfMemory)),
+      ErrorClass::Encode
+    );
+    assert_eq!(
+      class_of(FvpError::Zip(ZipError::FileNotFound)),
+      ErrorClass::Encode
+    );
+  }
+
+  #[test]
+  fn classify_error_with_context() {
+    let error = Err::<(), _>(FvpError::OffsetTooLarge)
+      .context("Failed to parse voice.bin")
+      .context("Failed to unpack voice.bin")
+      .unwrap_err();
+
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Format);
+    assert_eq!(ErrorClass::of(&error).exit_code(), 4);
+  }
+}
@@ -1,15 +1,34 @@
+mod cache;
 mod cli;
 mod commands;
+mod error;
+mod progress;
 mod utils;
 
 use clap::Parser;
+use tracing::level_filters::LevelFilter;
 
 use cli::Cli;
 
 fn main() {
   let cli = Cli::parse();
 
-  if let Err(error) = commands::run(&cli) {
-    eprintln!("Error: {error}");
+  let level = if cli.quiet {
+    LevelFilter::ERROR
+  } else if cli.verbose {
+    LevelFilter::DEBUG
+  } else {
+    LevelFilter::WARN
+  };
+
+  tracing_subscriber::fmt()
+    .with_max_level(level)
+    .with_target(false)
+    .without_time()
+    .with_writer(std::io::stderr)
+    .init();
+
+  if let Err(error) = commands::run(&cli.command) {
+    std::process::exit(error::report(&error, cli.error_format));
   }
 }
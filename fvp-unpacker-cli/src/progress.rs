@@ -0,0 +1,55 @@
+use std::{
+  io::{self, IsTerminal},
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use tracing::{debug, level_filters::LevelFilter};
+
+/// Progress of the entries being processed in parallel, drawn as a bar of the bytes done.
+pub struct EntryProgress {
+  bar: ProgressBar,
+  total: usize,
+  done: AtomicUsize,
+}
+
+impl EntryProgress {
+  /// The bar is only drawn when stdout is a terminal and the log level is the default, as
+  /// `--quiet` hides it and `--verbose` logs every entry instead.
+  pub fn new(total: usize, total_bytes: u64) -> Self {
+    let bar = if io::stdout().is_terminal() && LevelFilter::current() == LevelFilter::WARN {
+      ProgressBar::new(total_bytes)
+    } else {
+      ProgressBar::hidden()
+    };
+
+    bar.set_style(
+      ProgressStyle::with_template(
+        "[{elapsed_precise}] {wide_bar} {binary_bytes}/{binary_total_bytes} ({msg}, ETA {eta})",
+      )
+      .expect("valid template"),
+    );
+    bar.set_message(format!("0/{total} entries"));
+
+    Self {
+      bar,
+      total,
+      done: AtomicUsize::new(0),
+    }
+  }
+
+  /// Mark an entry of `bytes` as done.
+  pub fn inc(&self, name: &str, bytes: usize) {
+    let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+    debug!("Processed {name} ({done}/{})", self.total);
+
+    self.bar.inc(bytes as u64);
+    self
+      .bar
+      .set_message(format!("{done}/{} entries", self.total));
+  }
+
+  pub fn finish(&self) {
+    self.bar.finish_and_clear();
+  }
+}
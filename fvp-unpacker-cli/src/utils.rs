@@ -1,3 +1,16 @@
+use std::{
+  fs::{self, File},
+  io::{self, BufWriter},
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use anyhow::{Context, Result};
+use fvp_unpacker_core::{archive::hzc::FvpHzcEntry, image::atlas::FvpAtlas, prelude::*};
+use memmap2::Mmap;
+use rgb::{Bgr, Bgra, Gray};
+use serde_json::json;
+
 pub fn human_readable_size(size: usize) -> String {
   let mut size = size as f64;
   let mut scale = 0;
@@ -12,3 +25,204 @@ pub fn human_readable_size(size: usize) -> String {
 
   format!("{:.2} {}", size, UNITS[scale])
 }
+
+/// Format the duration like `3:05.250`.
+pub fn format_duration(duration: Duration) -> String {
+  let millis = duration.as_millis();
+  format!(
+    "{}:{:02}.{:03}",
+    millis / 60_000,
+    millis / 1000 % 60,
+    millis % 1000
+  )
+}
+
+pub fn csv_row(fields: &[impl AsRef<str>]) -> String {
+  fields
+    .iter()
+    .map(|field| {
+      let field = field.as_ref();
+
+      if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+      } else {
+        field.to_string()
+      }
+    })
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+/// Parse a layer as `NAME[:FRAME]`, where the frame defaults to the first one.
+pub fn parse_layer(layer: &str) -> Result<(String, usize), String> {
+  match layer.rsplit_once(':') {
+    Some((name, frame)) => match frame.parse() {
+      Ok(frame) => Ok((name.to_string(), frame)),
+      Err(_) => Err(format!("invalid frame index `{frame}`")),
+    },
+    None => Ok((layer.to_string(), 0)),
+  }
+}
+
+/// Find the files with the given extensions in `dir` and its subdirectories, sorted by path.
+pub fn find_files(dir: &Path, extensions: &[&str]) -> io::Result<Vec<PathBuf>> {
+  let mut files = Vec::new();
+
+  for entry in fs::read_dir(dir)? {
+    let path = entry?.path();
+
+    if path.is_dir() {
+      files.extend(find_files(&path, extensions)?);
+    } else if path
+      .extension()
+      .is_some_and(|ext| extensions.iter().any(|x| ext.eq_ignore_ascii_case(x)))
+    {
+      files.push(path);
+    }
+  }
+
+  files.sort();
+
+  Ok(files)
+}
+
+/// Map the file at `path` into memory.
+fn map_file(path: &Path) -> Result<Mmap> {
+  let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+  // SAFETY: it's not my fault :(
+  unsafe { Mmap::map(&file) }.with_context(|| format!("Failed to map {}", path.display()))
+}
+
+/// Open the `.bin` archive at `path`.
+pub fn open_archive(path: &Path) -> Result<FvpBin> {
+  FvpBin::parse(map_file(path)?).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Open the `.hcb` script at `path`.
+pub fn open_script(path: &Path) -> Result<FvpHcb> {
+  FvpHcb::parse(map_file(path)?).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Whether `path` is a `.hcb` script, rather than a `.bin` archive.
+pub fn is_script(path: &Path) -> bool {
+  path
+    .extension()
+    .is_some_and(|ext| ext.eq_ignore_ascii_case("hcb"))
+}
+
+/// Write the image as PNG, optionally trimmed to its non-transparent pixels with a sidecar JSON
+/// recording where it was cropped from. Returns the written files.
+pub fn write_bgra_png(
+  image: &FvpHzcEntry<Bgra<u8>>,
+  output_path: &Path,
+  trim: bool,
+) -> Result<Vec<PathBuf>> {
+  if !trim {
+    let output_file = File::create(output_path)?;
+    image.write_to_png(BufWriter::new(output_file))?;
+    return Ok(vec![output_path.to_path_buf()]);
+  }
+
+  let (trimmed, trim) = image.trim();
+
+  let output_file = File::create(output_path)?;
+  trimmed.write_to_png(BufWriter::new(output_file))?;
+
+  let sidecar = json!({
+    "trim": trim,
+    "offset": [trimmed.offset.0, trimmed.offset.1],
+  });
+  let sidecar_path = output_path.with_extension("json");
+  let sidecar_file = File::create(&sidecar_path)?;
+  serde_json::to_writer_pretty(BufWriter::new(sidecar_file), &sidecar)?;
+
+  Ok(vec![output_path.to_path_buf(), sidecar_path])
+}
+
+/// Pixels of the images which can be packed into an atlas.
+pub trait AtlasPixel: Copy + Default {
+  /// Name of the pixel format in TexturePacker
+  const FORMAT: &str;
+
+  fn write_png(image: &FvpHzcEntry<Self>, output_path: &Path) -> Result<()>;
+}
+
+impl AtlasPixel for Bgr<u8> {
+  const FORMAT: &str = "RGB888";
+
+  fn write_png(image: &FvpHzcEntry<Self>, output_path: &Path) -> Result<()> {
+    image.write_to_png(BufWriter::new(File::create(output_path)?))?;
+    Ok(())
+  }
+}
+
+impl AtlasPixel for Bgra<u8> {
+  const FORMAT: &str = "RGBA8888";
+
+  fn write_png(image: &FvpHzcEntry<Self>, output_path: &Path) -> Result<()> {
+    image.write_to_png(BufWriter::new(File::create(output_path)?))?;
+    Ok(())
+  }
+}
+
+impl AtlasPixel for Gray<u8> {
+  // 8-bit intensity, as named by cocos2d
+  const FORMAT: &str = "I8";
+
+  fn write_png(image: &FvpHzcEntry<Self>, output_path: &Path) -> Result<()> {
+    image.write_to_png(BufWriter::new(File::create(output_path)?))?;
+    Ok(())
+  }
+}
+
+/// Write the JSON descriptor of the atlas next to `output_path` in the JSON (hash) format of
+/// TexturePacker, and return its path. Every frame is treated as trimmed from a `source_size` image
+/// at its offset.
+pub fn write_atlas_json<Pixel: AtlasPixel>(
+  atlas: &FvpAtlas<Pixel>,
+  output_path: &Path,
+  source_size: (usize, usize),
+) -> Result<PathBuf> {
+  let frames: serde_json::Map<_, _> = atlas
+    .frames()
+    .iter()
+    .map(|frame| {
+      let trimmed = frame.offset != (0, 0) || (frame.width, frame.height) != source_size;
+
+      (
+        frame.name.clone(),
+        json!({
+          "frame": { "x": frame.x, "y": frame.y, "w": frame.width, "h": frame.height },
+          "rotated": false,
+          "trimmed": trimmed,
+          "spriteSourceSize": {
+            "x": frame.offset.0,
+            "y": frame.offset.1,
+            "w": frame.width,
+            "h": frame.height,
+          },
+          "sourceSize": { "w": source_size.0, "h": source_size.1 },
+        }),
+      )
+    })
+    .collect();
+
+  let image = atlas.image();
+  let descriptor = json!({
+    "frames": frames,
+    "meta": {
+      "app": env!("CARGO_PKG_NAME"),
+      "version": "1.0",
+      "image": output_path.file_name().map(|name| name.to_string_lossy()),
+      "format": Pixel::FORMAT,
+      "size": { "w": image.data.width(), "h": image.data.height() },
+      "scale": "1",
+    },
+  });
+
+  let json_path = output_path.with_extension("json");
+  let json_file = File::create(&json_path)?;
+  serde_json::to_writer_pretty(BufWriter::new(json_file), &descriptor)?;
+
+  Ok(json_path)
+}
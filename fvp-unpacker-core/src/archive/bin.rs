@@ -1,19 +1,24 @@
 use std::{borrow::Cow, io::Write};
 
 use crate::{
-  error::FvpResult,
+  error::{FvpError, FvpResult},
   utils::{encoding::encode_string, sread::FvpBuffer},
 };
 
 pub struct FvpBinEntry {
   filename: String,
   data: Box<[u8]>,
+  offset: Option<usize>,
 }
 
 impl FvpBinEntry {
   pub fn new(filename: impl Into<String>, data: impl Into<Box<[u8]>>) -> Self {
     fn new_inner(filename: String, data: Box<[u8]>) -> FvpBinEntry {
-      FvpBinEntry { filename, data }
+      FvpBinEntry {
+        filename,
+        data,
+        offset: None,
+      }
     }
 
     let filename = filename.into();
@@ -28,6 +33,11 @@ impl FvpBinEntry {
   pub fn data(&self) -> &[u8] {
     &self.data
   }
+
+  /// Offset of the data in the archive it was parsed from.
+  pub fn offset(&self) -> Option<usize> {
+    self.offset
+  }
 }
 
 #[derive(Default)]
@@ -76,7 +86,13 @@ impl FvpBin {
           let offset = src.sread::<u32>(index_offset + 4)? as usize;
           let size = src.sread::<u32>(index_offset + 8)? as usize;
 
-          Ok(FvpBinEntry::new(filename, &src[offset..(offset + size)]))
+          let data = src
+            .get(offset..(offset + size))
+            .ok_or(FvpError::OffsetTooLarge)?;
+
+          let mut entry = FvpBinEntry::new(filename, data);
+          entry.offset = Some(offset);
+          Ok(entry)
         })
         .collect::<FvpResult<_>>()?;
 
@@ -0,0 +1,32 @@
+use std::collections::BTreeMap;
+
+use super::bin::{FvpBin, FvpBinEntry};
+
+/// The leading part of the name before any digit or `_`, e.g. `yuk` of `yuk0001`, which usually
+/// tells the speaker of a voice.
+pub fn name_prefix(name: &str) -> Option<&str> {
+  let end = name
+    .find(|c: char| c.is_ascii_digit() || c == '_')
+    .unwrap_or(name.len());
+
+  Some(&name[..end]).filter(|prefix| !prefix.is_empty())
+}
+
+impl FvpBin {
+  /// Group the entries by the key of their names, in the order of the keys. Entries without a key
+  /// are left out.
+  pub fn group_by<'a, K: Ord>(
+    &'a self,
+    key: impl Fn(&'a str) -> Option<K>,
+  ) -> BTreeMap<K, Vec<&'a FvpBinEntry>> {
+    let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+
+    for entry in self.entries() {
+      if let Some(key) = key(entry.filename()) {
+        groups.entry(key).or_default().push(entry);
+      }
+    }
+
+    groups
+  }
+}
@@ -152,6 +152,52 @@ impl<Pixel> FvpHzc<Pixel> {
   }
 }
 
+pub struct FvpHzcHeader {
+  // signature: u32,
+  // unknown1: u16,
+  pub color: u16,
+  pub width: u16,
+  pub height: u16,
+  pub offset_x: u16,
+  pub offset_y: u16,
+  // unknown2: u32,
+  pub count: u32,
+  // unknown3: u64,
+}
+
+impl FvpHzcHeader {
+  fn parse(src: &[u8]) -> FvpResult<Self> {
+    let signature: u32 = src.sread(0)?;
+
+    if signature != u32::from_le_bytes(*b"NVSG") {
+      return Err(FvpError::FormatMismatch {
+        format: "Hzc header",
+        expected: b"NVSG",
+        found: Box::from(&src[..4]),
+      });
+    }
+
+    let color: u16 = src.sread(6)?;
+    let width: u16 = src.sread(8)?;
+    let height: u16 = src.sread(10)?;
+    let offset_x: u16 = src.sread(12)?;
+    let offset_y: u16 = src.sread(14)?;
+    let count: u32 = match src.sread(20)? {
+      0 => 1,
+      x => x,
+    };
+
+    Ok(Self {
+      color,
+      width,
+      height,
+      offset_x,
+      offset_y,
+      count,
+    })
+  }
+}
+
 pub enum DynamicFvpHzc {
   Bgr(FvpHzc<Bgr<u8>>),
   Bgra(FvpHzc<Bgra<u8>>),
@@ -161,6 +207,31 @@ pub enum DynamicFvpHzc {
 }
 
 impl DynamicFvpHzc {
+  /// Parse only the header, without decompressing the images.
+  pub fn parse_header(src: impl AsRef<[u8]>) -> FvpResult<FvpHzcHeader> {
+    fn parse_header_inner(src: &[u8]) -> FvpResult<FvpHzcHeader> {
+      let signature: u32 = src.sread(0)?;
+
+      if signature != u32::from_le_bytes(*b"hzc1") {
+        return Err(FvpError::FormatMismatch {
+          format: "Hzc file",
+          expected: b"hzc1",
+          found: Box::from(&src[..4]),
+        });
+      }
+
+      let header_size = src.sread::<u32>(8)? as usize;
+      let header = src
+        .get(12..12 + header_size)
+        .ok_or(FvpError::OffsetTooLarge)?;
+
+      FvpHzcHeader::parse(header)
+    }
+
+    let src = src.as_ref();
+    parse_header_inner(src)
+  }
+
   // TODO: zero-copy
   pub fn parse(src: impl AsRef<[u8]>) -> FvpResult<Self> {
     fn parse_inner(src: &[u8]) -> FvpResult<DynamicFvpHzc> {
@@ -179,52 +250,6 @@ impl DynamicFvpHzc {
 
       let data_index = 12 + header_size;
 
-      struct FvpHzcHeader {
-        // signature: u32,
-        // unknown1: u16,
-        color: u16,
-        width: u16,
-        height: u16,
-        offset_x: u16,
-        offset_y: u16,
-        // unknown2: u32,
-        count: u32,
-        // unknown3: u64,
-      }
-
-      impl FvpHzcHeader {
-        fn parse(src: &[u8]) -> FvpResult<Self> {
-          let signature: u32 = src.sread(0)?;
-
-          if signature != u32::from_le_bytes(*b"NVSG") {
-            return Err(FvpError::FormatMismatch {
-              format: "Hzc header",
-              expected: b"NVSG",
-              found: Box::from(&src[..4]),
-            });
-          }
-
-          let color: u16 = src.sread(6)?;
-          let width: u16 = src.sread(8)?;
-          let height: u16 = src.sread(10)?;
-          let offset_x: u16 = src.sread(12)?;
-          let offset_y: u16 = src.sread(14)?;
-          let count: u32 = match src.sread(20)? {
-            0 => 1,
-            x => x,
-          };
-
-          Ok(Self {
-            color,
-            width,
-            height,
-            offset_x,
-            offset_y,
-            count,
-          })
-        }
-      }
-
       let header = FvpHzcHeader::parse(&src[12..data_index])?;
 
       let data = {
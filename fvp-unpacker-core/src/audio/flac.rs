@@ -0,0 +1,226 @@
+//! A minimal FLAC encoder, see [RFC 9639](https://www.rfc-editor.org/rfc/rfc9639).
+//!
+//! Every channel is coded independently with the best fixed predictor and a single Rice
+//! partition, which is simple but still compresses well enough for music.
+
+use std::io::Write;
+
+use super::pcm::FvpPcm;
+use crate::error::FvpResult;
+
+const BLOCK_SIZE: usize = 4096;
+const BITS_PER_SAMPLE: u32 = 16;
+const MAX_RICE_PARAMETER: u32 = 14;
+
+#[derive(Default)]
+struct BitWriter {
+  bytes: Vec<u8>,
+  current: u64,
+  bits: u32,
+}
+
+impl BitWriter {
+  fn write(&mut self, value: u64, bits: u32) {
+    for i in (0..bits).rev() {
+      self.current = (self.current << 1) | ((value >> i) & 1);
+      self.bits += 1;
+
+      if self.bits == 8 {
+        self.bytes.push(self.current as u8);
+        self.current = 0;
+        self.bits = 0;
+      }
+    }
+  }
+
+  fn write_signed(&mut self, value: i64, bits: u32) {
+    self.write(value as u64 & ((1 << bits) - 1), bits);
+  }
+
+  fn write_unary(&mut self, zeros: u64) {
+    for _ in 0..zeros {
+      self.write(0, 1);
+    }
+    self.write(1, 1);
+  }
+
+  fn align(&mut self) {
+    if self.bits > 0 {
+      self.write(0, 8 - self.bits);
+    }
+  }
+}
+
+fn crc8(data: &[u8]) -> u8 {
+  data.iter().fold(0, |crc, &byte| {
+    (0..8).fold(crc ^ byte, |crc, _| {
+      if crc & 0x80 != 0 {
+        (crc << 1) ^ 0x07
+      } else {
+        crc << 1
+      }
+    })
+  })
+}
+
+fn crc16(data: &[u8]) -> u16 {
+  data.iter().fold(0, |crc, &byte| {
+    (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+      if crc & 0x8000 != 0 {
+        (crc << 1) ^ 0x8005
+      } else {
+        crc << 1
+      }
+    })
+  })
+}
+
+/// Residuals of the fixed predictor of `order`.
+fn fixed_residuals(samples: &[i32], order: usize) -> Vec<i32> {
+  (order..samples.len())
+    .map(|i| {
+      let s = |j: usize| samples[i - j];
+      match order {
+        0 => s(0),
+        1 => s(0) - s(1),
+        2 => s(0) - 2 * s(1) + s(2),
+        3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+        _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+      }
+    })
+    .collect()
+}
+
+fn zigzag(x: i32) -> u64 {
+  ((x << 1) ^ (x >> 31)) as u32 as u64
+}
+
+/// The Rice parameter taking the fewest bits, and the number of bits.
+fn rice_parameter(residuals: &[i32]) -> (u32, u64) {
+  (0..=MAX_RICE_PARAMETER)
+    .map(|k| {
+      let bits = residuals
+        .iter()
+        .map(|&x| (zigzag(x) >> k) + 1 + k as u64)
+        .sum();
+      (k, bits)
+    })
+    .min_by_key(|&(_, bits)| bits)
+    .unwrap_or((0, 0))
+}
+
+fn write_subframe(writer: &mut BitWriter, samples: &[i32]) {
+  let order = (0..=4.min(samples.len().saturating_sub(1)))
+    .map(|order| (order, rice_parameter(&fixed_residuals(samples, order))))
+    .min_by_key(|(_, (_, bits))| *bits);
+
+  let Some((order, (k, bits))) = order else {
+    return;
+  };
+
+  // fall back to verbatim when the prediction does not help
+  if bits + (order as u64 * BITS_PER_SAMPLE as u64) >= samples.len() as u64 * 16 {
+    writer.write(0b0000_0010, 8);
+    for &sample in samples {
+      writer.write_signed(sample.into(), BITS_PER_SAMPLE);
+    }
+    return;
+  }
+
+  writer.write(0b0001_0000 | (order as u64) << 1, 8);
+  for &sample in &samples[..order] {
+    writer.write_signed(sample.into(), BITS_PER_SAMPLE);
+  }
+
+  // Rice coding with 4-bit parameters in a single partition
+  writer.write(0, 2);
+  writer.write(0, 4);
+  writer.write(k.into(), 4);
+  for x in fixed_residuals(samples, order) {
+    let x = zigzag(x);
+    writer.write_unary(x >> k);
+    writer.write(x & ((1 << k) - 1), k);
+  }
+}
+
+/// The frame number in the UTF-8 like coding of FLAC.
+fn write_frame_number(writer: &mut BitWriter, number: u64) {
+  if number < 0x80 {
+    writer.write(number, 8);
+    return;
+  }
+
+  let mut continuation = Vec::new();
+  let mut rest = number;
+  while rest >= (1 << (6 - continuation.len())) {
+    continuation.push(0x80 | (rest & 0x3f));
+    rest >>= 6;
+  }
+
+  let count = continuation.len() as u32;
+  let prefix = (0xff00u64 >> (count + 1)) & 0xff;
+  writer.write(prefix | rest, 8);
+  for byte in continuation.into_iter().rev() {
+    writer.write(byte, 8);
+  }
+}
+
+impl FvpPcm {
+  /// Write the audio as a 16-bit FLAC file.
+  pub fn write_flac<W: Write>(&self, mut writer: W) -> FvpResult<()> {
+    let channels = self.channels.clamp(1, 8) as usize;
+    let frames = self.frames();
+
+    let mut header = BitWriter::default();
+    header.write(BLOCK_SIZE as u64, 16);
+    header.write(BLOCK_SIZE as u64, 16);
+    // unknown minimum and maximum frame sizes
+    header.write(0, 24);
+    header.write(0, 24);
+    header.write(self.sample_rate.into(), 20);
+    header.write(channels as u64 - 1, 3);
+    header.write(BITS_PER_SAMPLE as u64 - 1, 5);
+    header.write(frames as u64, 36);
+    // unknown MD5
+    header.write(0, 64);
+    header.write(0, 64);
+
+    writer.write_all(b"fLaC")?;
+    // the last metadata block, which is STREAMINFO
+    writer.write_all(&[0x80, 0, 0, 34])?;
+    writer.write_all(&header.bytes)?;
+
+    for (number, start) in (0..frames).step_by(BLOCK_SIZE).enumerate() {
+      let size = BLOCK_SIZE.min(frames - start);
+
+      let mut frame = BitWriter::default();
+      // sync code with fixed block size
+      frame.write(0xfff8, 16);
+      // 16-bit block size at the end of the header, sample rate from STREAMINFO
+      frame.write(0b0111, 4);
+      frame.write(0b0000, 4);
+      // independent channels, 16 bits per sample
+      frame.write(channels as u64 - 1, 4);
+      frame.write(0b100, 3);
+      frame.write(0, 1);
+      write_frame_number(&mut frame, number as u64);
+      frame.write(size as u64 - 1, 16);
+      frame.write(crc8(&frame.bytes).into(), 8);
+
+      for channel in 0..channels {
+        let samples: Vec<i32> = (start..(start + size))
+          .map(|i| self.samples[i * self.channels as usize + channel].into())
+          .collect();
+        write_subframe(&mut frame, &samples);
+      }
+
+      frame.align();
+      let crc = crc16(&frame.bytes);
+      frame.write(crc.into(), 16);
+
+      writer.write_all(&frame.bytes)?;
+    }
+
+    Ok(())
+  }
+}
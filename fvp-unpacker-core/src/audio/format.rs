@@ -0,0 +1,88 @@
+//! Codec and format of Ogg streams, read from the identification header.
+
+use std::fmt;
+
+use super::ogg::{FvpOggPages, first_packets};
+use crate::{
+  error::{FvpError, FvpResult},
+  utils::sread::FvpBuffer,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum FvpAudioCodec {
+  Vorbis,
+  Opus,
+}
+
+impl fmt::Display for FvpAudioCodec {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Vorbis => write!(f, "Vorbis"),
+      Self::Opus => write!(f, "Opus"),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FvpAudioFormat {
+  pub codec: FvpAudioCodec,
+  pub channels: u8,
+  /// For Opus, the sample rate of the original input, as it is always decoded at 48 kHz
+  pub sample_rate: u32,
+}
+
+impl FvpAudioFormat {
+  pub fn parse(src: impl AsRef<[u8]>) -> FvpResult<Self> {
+    fn parse_inner(src: &[u8]) -> FvpResult<FvpAudioFormat> {
+      let packets = first_packets(FvpOggPages::new(src), 1)?;
+      let packet = packets.first().ok_or(FvpError::OffsetTooLarge)?;
+
+      if let Some(header) = packet.strip_prefix(b"\x01vorbis") {
+        Ok(FvpAudioFormat {
+          codec: FvpAudioCodec::Vorbis,
+          channels: header.sread(4)?,
+          sample_rate: header.sread(5)?,
+        })
+      } else if let Some(header) = packet.strip_prefix(b"OpusHead") {
+        let sample_rate: u32 = header.sread(4)?;
+
+        Ok(FvpAudioFormat {
+          codec: FvpAudioCodec::Opus,
+          channels: header.sread(1)?,
+          // 0 if the input sample rate is unspecified
+          sample_rate: if sample_rate == 0 { 48000 } else { sample_rate },
+        })
+      } else {
+        Err(FvpError::UnknownAudioCodec)
+      }
+    }
+
+    let src = src.as_ref();
+    parse_inner(src)
+  }
+
+  /// Describe how `other` differs from this format, e.g. to check a replacement of an entry.
+  pub fn mismatches(&self, other: &Self) -> Vec<String> {
+    let mut mismatches = Vec::new();
+
+    if self.codec != other.codec {
+      mismatches.push(format!("codec {} instead of {}", other.codec, self.codec));
+    }
+    if self.channels != other.channels {
+      mismatches.push(format!(
+        "{} channels instead of {}",
+        other.channels, self.channels
+      ));
+    }
+    if self.sample_rate != other.sample_rate {
+      mismatches.push(format!(
+        "sample rate {} Hz instead of {} Hz",
+        other.sample_rate, self.sample_rate
+      ));
+    }
+
+    mismatches
+  }
+}
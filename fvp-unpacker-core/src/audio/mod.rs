@@ -0,0 +1,6 @@
+pub mod flac;
+pub mod format;
+pub mod ogg;
+pub mod pcm;
+pub mod verify;
+pub mod vorbis;
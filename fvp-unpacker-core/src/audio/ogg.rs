@@ -0,0 +1,178 @@
+//! Ogg container, see [RFC 3533](https://www.rfc-editor.org/rfc/rfc3533).
+
+use crate::{
+  error::{FvpError, FvpResult},
+  utils::sread::FvpBuffer,
+};
+
+const HEADER_SIZE: usize = 27;
+
+/// A page of an Ogg stream, borrowing its data.
+pub struct FvpOggPage<'a> {
+  pub version: u8,
+  pub header_type: u8,
+  /// `u64::MAX` if no packet finishes on this page
+  pub granule_position: u64,
+  pub serial_number: u32,
+  pub sequence_number: u32,
+  pub checksum: u32,
+  /// Lacing values of the segments
+  pub segments: &'a [u8],
+  pub data: &'a [u8],
+  raw: &'a [u8],
+}
+
+impl<'a> FvpOggPage<'a> {
+  /// Parse the page at the start of `src`.
+  pub fn parse(src: &'a [u8]) -> FvpResult<Self> {
+    if src.len() < HEADER_SIZE {
+      return Err(FvpError::OffsetTooLarge);
+    }
+
+    if &src[..4] != b"OggS" {
+      return Err(FvpError::FormatMismatch {
+        format: "Ogg page",
+        expected: b"OggS",
+        found: Box::from(&src[..4]),
+      });
+    }
+
+    let segment_count = src[26] as usize;
+    let segments = src
+      .get(HEADER_SIZE..(HEADER_SIZE + segment_count))
+      .ok_or(FvpError::OffsetTooLarge)?;
+
+    let data_offset = HEADER_SIZE + segment_count;
+    let data_size: usize = segments.iter().map(|&x| x as usize).sum();
+    let data = src
+      .get(data_offset..(data_offset + data_size))
+      .ok_or(FvpError::OffsetTooLarge)?;
+    let raw = &src[..(data_offset + data_size)];
+
+    Ok(Self {
+      version: src[4],
+      header_type: src[5],
+      granule_position: src.sread(6)?,
+      serial_number: src.sread(14)?,
+      sequence_number: src.sread(18)?,
+      checksum: src.sread(22)?,
+      segments,
+      data,
+      raw,
+    })
+  }
+
+  /// Size of the whole page in bytes.
+  pub fn size(&self) -> usize {
+    self.raw.len()
+  }
+
+  /// The CRC-32 of the page as stored in `checksum`, i.e. computed with the checksum field as
+  /// zeros.
+  pub fn compute_checksum(&self) -> u32 {
+    let update = |crc: u32, byte: u8| {
+      (0..8).fold(crc ^ ((byte as u32) << 24), |crc, _| {
+        if crc & 0x8000_0000 != 0 {
+          (crc << 1) ^ 0x04c1_1db7
+        } else {
+          crc << 1
+        }
+      })
+    };
+
+    self.raw[..22]
+      .iter()
+      .copied()
+      .chain([0; 4])
+      .chain(self.raw[26..].iter().copied())
+      .fold(0, update)
+  }
+
+  /// The first packet on this page continues from the previous page.
+  pub fn is_continued(&self) -> bool {
+    self.header_type & 0x01 != 0
+  }
+
+  /// Beginning of stream.
+  pub fn is_first(&self) -> bool {
+    self.header_type & 0x02 != 0
+  }
+
+  /// End of stream.
+  pub fn is_last(&self) -> bool {
+    self.header_type & 0x04 != 0
+  }
+}
+
+/// Iterate over the pages of an Ogg stream. Iteration stops after the first error.
+pub struct FvpOggPages<'a> {
+  src: &'a [u8],
+  offset: usize,
+  failed: bool,
+}
+
+impl<'a> FvpOggPages<'a> {
+  pub fn new(src: &'a [u8]) -> Self {
+    Self {
+      src,
+      offset: 0,
+      failed: false,
+    }
+  }
+
+  /// Offset of the next page.
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+}
+
+impl<'a> Iterator for FvpOggPages<'a> {
+  type Item = FvpResult<FvpOggPage<'a>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.failed || self.offset >= self.src.len() {
+      return None;
+    }
+
+    match FvpOggPage::parse(&self.src[self.offset..]) {
+      Ok(page) => {
+        self.offset += page.size();
+        Some(Ok(page))
+      }
+      Err(error) => {
+        self.failed = true;
+        Some(Err(error))
+      }
+    }
+  }
+}
+
+/// Reassemble the first `count` packets from the pages, e.g. to read the codec headers.
+pub fn first_packets<'a>(
+  pages: impl IntoIterator<Item = FvpResult<FvpOggPage<'a>>>,
+  count: usize,
+) -> FvpResult<Vec<Vec<u8>>> {
+  let mut packets = Vec::new();
+  let mut packet = Vec::new();
+
+  for page in pages {
+    let page = page?;
+    let mut offset = 0;
+
+    for &lacing in page.segments {
+      packet.extend_from_slice(&page.data[offset..(offset + lacing as usize)]);
+      offset += lacing as usize;
+
+      // a lacing value less than 255 ends the packet
+      if lacing < 255 {
+        packets.push(std::mem::take(&mut packet));
+
+        if packets.len() == count {
+          return Ok(packets);
+        }
+      }
+    }
+  }
+
+  Ok(packets)
+}
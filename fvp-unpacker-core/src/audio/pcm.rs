@@ -0,0 +1,115 @@
+use std::{
+  io::{Cursor, Write},
+  time::Duration,
+};
+
+use lewton::inside_ogg::OggStreamReader;
+
+use crate::error::FvpResult;
+
+/// 16-bit PCM audio, with the samples of the channels interleaved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FvpPcm {
+  pub sample_rate: u32,
+  pub channels: u16,
+  pub samples: Vec<i16>,
+}
+
+impl FvpPcm {
+  /// Decode a whole Ogg Vorbis stream.
+  pub fn decode_vorbis(src: impl AsRef<[u8]>) -> FvpResult<Self> {
+    fn decode_vorbis_inner(src: &[u8]) -> FvpResult<FvpPcm> {
+      let mut reader = OggStreamReader::new(Cursor::new(src))?;
+
+      let mut samples = Vec::new();
+      while let Some(packet) = reader.read_dec_packet_itl()? {
+        samples.extend_from_slice(&packet);
+      }
+
+      Ok(FvpPcm {
+        sample_rate: reader.ident_hdr.audio_sample_rate,
+        channels: reader.ident_hdr.audio_channels.into(),
+        samples,
+      })
+    }
+
+    let src = src.as_ref();
+    decode_vorbis_inner(src)
+  }
+
+  /// Number of samples per channel.
+  pub fn frames(&self) -> usize {
+    self.samples.len() / self.channels.max(1) as usize
+  }
+
+  pub fn duration(&self) -> Duration {
+    match self.sample_rate {
+      0 => Duration::ZERO,
+      rate => Duration::from_secs_f64(self.frames() as f64 / rate as f64),
+    }
+  }
+
+  /// Render the intro, the loop `count` times, and then the beginning of the loop again with a
+  /// linear fade out of `fade`. The loop is given in samples per channel, and clamped to the
+  /// length of the audio.
+  pub fn looped(&self, loop_start: u64, loop_length: u64, count: usize, fade: Duration) -> Self {
+    let channels = self.channels.max(1) as usize;
+    let frames = self.frames();
+
+    let start = (loop_start as usize).min(frames);
+    let end = start.saturating_add(loop_length as usize).min(frames);
+    let body = &self.samples[(start * channels)..(end * channels)];
+
+    let mut samples = self.samples[..(end * channels)].to_vec();
+    for _ in 1..count {
+      samples.extend_from_slice(body);
+    }
+
+    let fade_frames = (fade.as_secs_f64() * self.sample_rate as f64) as usize;
+    if fade_frames > 0 && !body.is_empty() {
+      for i in 0..fade_frames {
+        let gain = 1.0 - i as f64 / fade_frames as f64;
+        let frame = (i % (end - start)) * channels;
+
+        samples.extend(
+          body[frame..(frame + channels)]
+            .iter()
+            .map(|&sample| (sample as f64 * gain).round() as i16),
+        );
+      }
+    }
+
+    Self {
+      sample_rate: self.sample_rate,
+      channels: self.channels,
+      samples,
+    }
+  }
+
+  /// Write the audio as a 16-bit PCM WAV file.
+  pub fn write_wav<W: Write>(&self, mut writer: W) -> FvpResult<()> {
+    let data_size = self.samples.len() as u32 * 2;
+    let block_align = self.channels * 2;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    // PCM
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&self.channels.to_le_bytes())?;
+    writer.write_all(&self.sample_rate.to_le_bytes())?;
+    writer.write_all(&(self.sample_rate * block_align as u32).to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    let data: Vec<u8> = self.samples.iter().flat_map(|x| x.to_le_bytes()).collect();
+    writer.write_all(&data)?;
+
+    Ok(())
+  }
+}
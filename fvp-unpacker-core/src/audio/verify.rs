@@ -0,0 +1,151 @@
+use std::{collections::HashMap, fmt};
+
+use super::{
+  format::{FvpAudioCodec, FvpAudioFormat},
+  ogg::FvpOggPages,
+  pcm::FvpPcm,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FvpOggProblem {
+  /// The page can not be parsed, e.g. it is truncated or has no `OggS` signature
+  Malformed(String),
+  ChecksumMismatch {
+    expected: u32,
+    found: u32,
+  },
+  SequenceMismatch {
+    expected: u32,
+    found: u32,
+  },
+  MissingEndOfStream,
+  /// Pages of a stream after its end-of-stream page
+  PageAfterEndOfStream,
+  /// The stream can not be decoded, which is not tied to a page
+  Decoding(String),
+}
+
+impl fmt::Display for FvpOggProblem {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Malformed(error) => write!(f, "malformed page ({error})"),
+      Self::ChecksumMismatch { expected, found } => {
+        write!(
+          f,
+          "checksum mismatch (expected {expected:#010x}, but found {found:#010x})"
+        )
+      }
+      Self::SequenceMismatch { expected, found } => {
+        write!(
+          f,
+          "sequence number mismatch (expected {expected}, but found {found})"
+        )
+      }
+      Self::MissingEndOfStream => write!(f, "missing end-of-stream page"),
+      Self::PageAfterEndOfStream => write!(f, "page after the end of stream"),
+      Self::Decoding(error) => write!(f, "can not decode ({error})"),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FvpOggIssue {
+  /// Index and byte offset of the page, if the problem is tied to one
+  pub page: Option<(usize, usize)>,
+  pub problem: FvpOggProblem,
+}
+
+impl fmt::Display for FvpOggIssue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.page {
+      Some((index, offset)) => write!(f, "page {index} at {offset:#x}: {}", self.problem),
+      None => write!(f, "{}", self.problem),
+    }
+  }
+}
+
+/// Check the checksum and sequence number of every page, and that every stream ends with an
+/// end-of-stream page. With `decode`, a Vorbis stream is also decoded fully, which is much
+/// slower, while only the pages of an Opus stream are checked as it can not be decoded. Checking
+/// stops at the first malformed page.
+pub fn verify_ogg(src: impl AsRef<[u8]>, decode: bool) -> Vec<FvpOggIssue> {
+  fn verify_ogg_inner(src: &[u8], decode: bool) -> Vec<FvpOggIssue> {
+    let mut issues = Vec::new();
+    // the next sequence number and whether it has ended, of each stream
+    let mut streams: HashMap<u32, (u32, bool)> = HashMap::new();
+
+    let mut pages = FvpOggPages::new(src);
+    let mut index = 0;
+
+    loop {
+      let offset = pages.offset();
+      let mut issue = |problem| {
+        issues.push(FvpOggIssue {
+          page: Some((index, offset)),
+          problem,
+        })
+      };
+
+      let page = match pages.next() {
+        Some(Ok(page)) => page,
+        Some(Err(error)) => {
+          issue(FvpOggProblem::Malformed(error.to_string()));
+          break;
+        }
+        None => break,
+      };
+
+      let checksum = page.compute_checksum();
+      if checksum != page.checksum {
+        issue(FvpOggProblem::ChecksumMismatch {
+          expected: page.checksum,
+          found: checksum,
+        });
+      }
+
+      let (sequence_number, ended) = streams
+        .entry(page.serial_number)
+        .or_insert((page.sequence_number, false));
+
+      if *ended {
+        issue(FvpOggProblem::PageAfterEndOfStream);
+      }
+      if page.sequence_number != *sequence_number {
+        issue(FvpOggProblem::SequenceMismatch {
+          expected: *sequence_number,
+          found: page.sequence_number,
+        });
+      }
+
+      *sequence_number = page.sequence_number.wrapping_add(1);
+      *ended |= page.is_last();
+      index += 1;
+    }
+
+    if streams.values().any(|(_, ended)| !ended) {
+      issues.push(FvpOggIssue {
+        page: None,
+        problem: FvpOggProblem::MissingEndOfStream,
+      });
+    }
+
+    let decoded = match FvpAudioFormat::parse(src) {
+      _ if !decode => Ok(()),
+      Ok(format) if format.codec == FvpAudioCodec::Opus => Ok(()),
+      Ok(_) => FvpPcm::decode_vorbis(src).map(drop),
+      Err(error) => Err(error),
+    };
+
+    if let Err(error) = decoded {
+      issues.push(FvpOggIssue {
+        page: None,
+        problem: FvpOggProblem::Decoding(error.to_string()),
+      });
+    }
+
+    issues
+  }
+
+  let src = src.as_ref();
+  verify_ogg_inner(src, decode)
+}
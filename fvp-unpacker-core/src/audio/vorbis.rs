@@ -0,0 +1,129 @@
+//! Headers of Vorbis streams in Ogg, see the
+//! [Vorbis I specification](https://xiph.org/vorbis/doc/Vorbis_I_spec.html).
+
+use std::time::Duration;
+
+use super::ogg::{FvpOggPages, first_packets};
+use crate::{
+  error::{FvpError, FvpResult},
+  utils::sread::FvpBuffer,
+};
+
+/// Metadata of a Vorbis stream, read from its headers without decoding the audio.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FvpVorbisInfo {
+  pub channels: u8,
+  pub sample_rate: u32,
+  pub vendor: String,
+  /// Comments as `(key, value)`, in the original order
+  pub comments: Vec<(String, String)>,
+  /// Total samples per channel, from the granule position of the last page
+  pub samples: u64,
+}
+
+fn read_header(packet: &[u8], packet_type: u8) -> FvpResult<&[u8]> {
+  match packet.split_first_chunk::<7>() {
+    Some(([x, b'v', b'o', b'r', b'b', b'i', b's'], rest)) if *x == packet_type => Ok(rest),
+    _ => Err(FvpError::InvalidVorbisHeader("packet type mismatch")),
+  }
+}
+
+fn read_string(src: &[u8], offset: &mut usize) -> FvpResult<String> {
+  let size = src.sread::<u32>(*offset)? as usize;
+  let string = src
+    .get((*offset + 4)..(*offset + 4 + size))
+    .ok_or(FvpError::InvalidVorbisHeader("comment too long"))?;
+
+  *offset += 4 + size;
+
+  Ok(String::from_utf8_lossy(string).into_owned())
+}
+
+impl FvpVorbisInfo {
+  pub fn parse(src: impl AsRef<[u8]>) -> FvpResult<Self> {
+    fn parse_inner(src: &[u8]) -> FvpResult<FvpVorbisInfo> {
+      let packets = first_packets(FvpOggPages::new(src), 2)?;
+      let [identification, comment] = packets.as_slice() else {
+        return Err(FvpError::InvalidVorbisHeader("missing headers"));
+      };
+
+      let identification = read_header(identification, 1)?;
+      if identification.len() < 23 {
+        return Err(FvpError::InvalidVorbisHeader(
+          "identification header too short",
+        ));
+      }
+      let channels: u8 = identification.sread(4)?;
+      let sample_rate: u32 = identification.sread(5)?;
+
+      let comment = read_header(comment, 3)?;
+      let mut offset = 0;
+      let vendor = read_string(comment, &mut offset)?;
+
+      let count: u32 = comment.sread(offset)?;
+      offset += 4;
+
+      let comments = (0..count)
+        .map(|_| {
+          let comment = read_string(comment, &mut offset)?;
+          Ok(match comment.split_once('=') {
+            Some((key, value)) => (key.to_string(), value.to_string()),
+            None => (comment, String::new()),
+          })
+        })
+        .collect::<FvpResult<_>>()?;
+
+      // only the header of each page is needed here
+      let samples = FvpOggPages::new(src)
+        .map_while(Result::ok)
+        .map(|page| page.granule_position)
+        .filter(|&granule_position| granule_position != u64::MAX)
+        .last()
+        .unwrap_or(0);
+
+      Ok(FvpVorbisInfo {
+        channels,
+        sample_rate,
+        vendor,
+        comments,
+        samples,
+      })
+    }
+
+    let src = src.as_ref();
+    parse_inner(src)
+  }
+
+  /// The value of the first comment with the key, which is case-insensitive.
+  pub fn comment(&self, key: &str) -> Option<&str> {
+    self
+      .comments
+      .iter()
+      .find(|(k, _)| k.eq_ignore_ascii_case(key))
+      .map(|(_, value)| value.as_str())
+  }
+
+  pub fn duration(&self) -> Duration {
+    match self.sample_rate {
+      0 => Duration::ZERO,
+      rate => Duration::from_secs_f64(self.samples as f64 / rate as f64),
+    }
+  }
+
+  /// The sample where the loop starts, from the `LOOPSTART` comment.
+  pub fn loop_start(&self) -> Option<u64> {
+    self.comment("LOOPSTART")?.trim().parse().ok()
+  }
+
+  /// Number of samples in the loop, from the `LOOPLENGTH` comment, or `LOOPEND` minus
+  /// `LOOPSTART`.
+  pub fn loop_length(&self) -> Option<u64> {
+    if let Some(length) = self.comment("LOOPLENGTH") {
+      return length.trim().parse().ok();
+    }
+
+    let end: u64 = self.comment("LOOPEND")?.trim().parse().ok()?;
+    end.checked_sub(self.loop_start()?)
+  }
+}
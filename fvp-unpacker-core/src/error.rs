@@ -27,9 +27,27 @@ pub enum FvpError {
   #[error("Decompressed data length mismatch (expected {expected}, but found {found})")]
   DecompressLengthMismatch { expected: usize, found: usize },
 
+  #[error("Invalid Vorbis header ({0})")]
+  InvalidVorbisHeader(&'static str),
+
+  #[error("Unknown audio codec")]
+  UnknownAudioCodec,
+
+  #[error(transparent)]
+  VorbisDecoding(#[from] lewton::VorbisError),
+
+  #[error("Unknown script opcode {opcode:#04x} at {address:#010x}")]
+  UnknownOpcode { address: u32, opcode: u8 },
+
   #[error(transparent)]
   ImageEncoding(#[from] png::EncodingError),
 
+  #[error(transparent)]
+  GifEncoding(#[from] gif::EncodingError),
+
+  #[error(transparent)]
+  Zip(#[from] zip::result::ZipError),
+
   #[error("Image width")]
   ImageWidthMismatch { expected: u16, found: usize },
 
@@ -0,0 +1,112 @@
+use std::io::Write;
+
+use gif::{DisposalMethod, Repeat};
+use png::ColorType;
+use rgb::{Bgra, Rgba};
+
+use crate::{
+  archive::hzc::{FvpHzc, FvpHzcEntry},
+  error::FvpResult,
+};
+
+/// Frames cycling forever, e.g. to preview every facial expression of a tachie.
+///
+/// The frames are placed at their offsets on the smallest canvas which all of them fit in.
+pub struct FvpAnimation {
+  width: usize,
+  height: usize,
+  frames: Vec<FvpHzcEntry<Bgra<u8>>>,
+  delay: u16,
+}
+
+impl FvpAnimation {
+  pub fn new(frames: impl IntoIterator<Item = FvpHzcEntry<Bgra<u8>>>) -> Self {
+    let mut frames: Vec<_> = frames.into_iter().collect();
+
+    let left = frames.iter().map(|frame| frame.offset.0).min().unwrap_or(0);
+    let top = frames.iter().map(|frame| frame.offset.1).min().unwrap_or(0);
+    for frame in &mut frames {
+      frame.offset = (frame.offset.0 - left, frame.offset.1 - top);
+    }
+
+    let (width, height) = frames
+      .iter()
+      .map(|frame| frame.canvas_size())
+      .fold((0, 0), |(width, height), (w, h)| {
+        (width.max(w), height.max(h))
+      });
+
+    let frames = frames
+      .iter()
+      .map(|frame| frame.place_on_canvas(width, height))
+      .collect();
+
+    Self {
+      width,
+      height,
+      frames,
+      delay: 500,
+    }
+  }
+
+  /// Set how long each frame is shown in milliseconds, 500 by default.
+  pub fn with_delay(mut self, delay: u16) -> Self {
+    self.delay = delay;
+    self
+  }
+
+  pub fn frames(&self) -> &[FvpHzcEntry<Bgra<u8>>] {
+    &self.frames
+  }
+
+  fn rgba(frame: &FvpHzcEntry<Bgra<u8>>) -> Vec<u8> {
+    let data: Box<[Rgba<u8>]> = frame.data.pixels().map(Into::into).collect();
+    bytemuck::cast_slice_box(data).into_vec()
+  }
+
+  /// Write the animation as APNG.
+  pub fn write_apng<W: Write>(&self, writer: W) -> FvpResult<()> {
+    let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_animated(self.frames.len() as u32, 0)?;
+    encoder.set_frame_delay(self.delay, 1000)?;
+
+    let mut writer = encoder.write_header()?;
+    for frame in &self.frames {
+      writer.write_image_data(&Self::rgba(frame))?;
+    }
+    writer.finish()?;
+
+    Ok(())
+  }
+
+  /// Write the animation as GIF. Colors are quantized to 256 per frame, and pixels which are not
+  /// fully opaque may lose their translucency.
+  pub fn write_gif<W: Write>(&self, writer: W) -> FvpResult<()> {
+    let mut encoder = gif::Encoder::new(writer, self.width as u16, self.height as u16, &[])?;
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    for frame in &self.frames {
+      let mut gif_frame = gif::Frame::from_rgba_speed(
+        self.width as u16,
+        self.height as u16,
+        &mut Self::rgba(frame),
+        10,
+      );
+      // GIF delays are in units of 10ms
+      gif_frame.delay = self.delay.div_ceil(10);
+      gif_frame.dispose = DisposalMethod::Background;
+
+      encoder.write_frame(&gif_frame)?;
+    }
+
+    Ok(())
+  }
+}
+
+impl FvpHzc<Bgra<u8>> {
+  /// Animate the images, e.g. of a facial expression sheet, in order.
+  pub fn animation(&self) -> FvpAnimation {
+    FvpAnimation::new(self.entries().iter().cloned())
+  }
+}
@@ -0,0 +1,108 @@
+use imgref::ImgVec;
+
+use crate::archive::hzc::FvpHzcEntry;
+
+/// Where a frame is placed in the atlas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FvpAtlasFrame {
+  pub name: String,
+  pub x: usize,
+  pub y: usize,
+  pub width: usize,
+  pub height: usize,
+  /// The original offset of the frame, i.e. relative to the base image
+  pub offset: (u16, u16),
+}
+
+/// Frames packed into a single texture.
+pub struct FvpAtlas<Pixel> {
+  image: FvpHzcEntry<Pixel>,
+  frames: Vec<FvpAtlasFrame>,
+}
+
+impl<Pixel: Copy + Default> FvpAtlas<Pixel> {
+  /// Pack the frames into rows (shelves) from the tallest to the shortest, with `padding` pixels
+  /// between them. The width of the atlas is about the square root of the total area, so that
+  /// the atlas is roughly square.
+  pub fn pack<'a>(
+    frames: impl IntoIterator<Item = (String, &'a FvpHzcEntry<Pixel>)>,
+    padding: usize,
+  ) -> Self
+  where
+    Pixel: 'a,
+  {
+    let mut frames: Vec<_> = frames.into_iter().collect();
+    frames.sort_by_key(|(_, image)| std::cmp::Reverse(image.data.height()));
+
+    let area: usize = frames
+      .iter()
+      .map(|(_, image)| (image.data.width() + padding) * (image.data.height() + padding))
+      .sum();
+    let widest = frames
+      .iter()
+      .map(|(_, image)| image.data.width())
+      .max()
+      .unwrap_or(0);
+    let max_width = widest.max(area.isqrt());
+
+    let mut placed = Vec::with_capacity(frames.len());
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    let (mut width, mut height) = (0, 0);
+
+    for (name, image) in frames {
+      let (frame_width, frame_height) = (image.data.width(), image.data.height());
+
+      if x > 0 && x + frame_width > max_width {
+        x = 0;
+        y += shelf_height + padding;
+        shelf_height = 0;
+      }
+
+      placed.push((
+        FvpAtlasFrame {
+          name,
+          x,
+          y,
+          width: frame_width,
+          height: frame_height,
+          offset: image.offset,
+        },
+        image,
+      ));
+
+      width = width.max(x + frame_width);
+      height = height.max(y + frame_height);
+      shelf_height = shelf_height.max(frame_height);
+      x += frame_width + padding;
+    }
+
+    let mut canvas = ImgVec::new(vec![Pixel::default(); width * height], width, height);
+    for (frame, image) in &placed {
+      canvas
+        .sub_image_mut(frame.x, frame.y, frame.width, frame.height)
+        .rows_mut()
+        .zip(image.data.rows())
+        .for_each(|(dst, src)| dst.copy_from_slice(src));
+    }
+
+    Self {
+      image: FvpHzcEntry {
+        offset: (0, 0),
+        data: canvas,
+      },
+      frames: placed.into_iter().map(|(frame, _)| frame).collect(),
+    }
+  }
+}
+
+impl<Pixel> FvpAtlas<Pixel> {
+  pub fn image(&self) -> &FvpHzcEntry<Pixel> {
+    &self.image
+  }
+
+  /// Frames in the order of packing.
+  pub fn frames(&self) -> &[FvpAtlasFrame] {
+    &self.frames
+  }
+}
@@ -0,0 +1,38 @@
+use imgref::ImgVec;
+
+use crate::archive::hzc::FvpHzcEntry;
+
+impl<Pixel: Copy + Default> FvpHzcEntry<Pixel> {
+  /// The smallest canvas, starting from `(0, 0)`, which the image fits in at its offset.
+  pub fn canvas_size(&self) -> (usize, usize) {
+    (
+      self.offset.0 as usize + self.data.width(),
+      self.offset.1 as usize + self.data.height(),
+    )
+  }
+
+  /// Copy the image onto a blank (transparent if it has alpha) canvas of `width` x `height` at
+  /// its offset. Pixels falling outside of the canvas are clipped. The canvas must not be empty.
+  pub fn place_on_canvas(&self, width: usize, height: usize) -> FvpHzcEntry<Pixel> {
+    let mut canvas = ImgVec::new(vec![Pixel::default(); width * height], width, height);
+
+    let left = (self.offset.0 as usize).min(width);
+    let top = (self.offset.1 as usize).min(height);
+    let right = (left + self.data.width()).min(width);
+    let bottom = (top + self.data.height()).min(height);
+
+    // Nothing to copy if the image is entirely off the canvas
+    if right > left && bottom > top {
+      canvas
+        .sub_image_mut(left, top, right - left, bottom - top)
+        .rows_mut()
+        .zip(self.data.rows())
+        .for_each(|(dst, src)| dst.copy_from_slice(&src[..(right - left)]));
+    }
+
+    FvpHzcEntry {
+      offset: (0, 0),
+      data: canvas,
+    }
+  }
+}
@@ -0,0 +1,71 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::archive::{bin::FvpBin, hzc::DynamicFvpHzc};
+
+/// A complete event CG, composed by stacking the layers from the bottom to the top.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FvpEventCg {
+  pub name: String,
+  /// Entry names and frame indices, the first one is the base
+  pub layers: Vec<(String, usize)>,
+}
+
+impl FvpBin {
+  /// Group the images whose names start with `prefix` by naming convention, i.e. `EV_001_a` is a
+  /// diff on top of `EV_001`, and `EV_001_a_1` is a diff on top of `EV_001_a` if it exists.
+  ///
+  /// Every frame of a diff results in a CG, named with a `-{frame}` suffix if there are more than
+  /// one. The first frame of the base (and of each intermediate diff) is used.
+  pub fn event_cgs(&self, prefix: &str) -> Vec<FvpEventCg> {
+    let counts: HashMap<_, _> = self
+      .entries()
+      .iter()
+      .filter(|entry| entry.filename().starts_with(prefix))
+      .filter_map(|entry| {
+        let header = DynamicFvpHzc::parse_header(entry.data()).ok()?;
+        Some((entry.filename(), header.count as usize))
+      })
+      .collect();
+
+    // the nearest existing name that `name` extends with `_suffix`
+    let parent = |name: &str| {
+      name
+        .char_indices()
+        .rev()
+        .filter(|&(i, c)| c == '_' && i > prefix.len())
+        .find_map(|(i, _)| counts.get_key_value(&name[..i]).map(|(base, _)| *base))
+    };
+
+    let mut cgs = BTreeMap::new();
+
+    for (&name, &count) in &counts {
+      let mut ancestors = Vec::new();
+      let mut current = name;
+      while let Some(base) = parent(current) {
+        ancestors.push((base.to_string(), 0));
+        current = base;
+      }
+      ancestors.reverse();
+
+      for frame in 0..count {
+        let cg_name = match count {
+          1 => name.to_string(),
+          _ => format!("{name}-{frame}"),
+        };
+
+        let mut layers = ancestors.clone();
+        layers.push((name.to_string(), frame));
+
+        cgs.insert(
+          cg_name.clone(),
+          FvpEventCg {
+            name: cg_name,
+            layers,
+          },
+        );
+      }
+    }
+
+    cgs.into_values().collect()
+  }
+}
@@ -0,0 +1,113 @@
+use imgref::ImgVec;
+use rgb::{Bgr, Bgra};
+
+use crate::archive::hzc::FvpHzcEntry;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FvpAlpha {
+  /// Color channels are independent of the alpha channel
+  #[default]
+  Straight,
+  /// Color channels are already multiplied by the alpha channel
+  Premultiplied,
+}
+
+fn over_straight(src: Bgra<u8>, dst: Bgra<u8>) -> Bgra<u8> {
+  let src_a = src.a as u32;
+  let dst_a = dst.a as u32 * (255 - src_a);
+  // alpha of the result, scaled by 255 * 255
+  let out_a = src_a * 255 + dst_a;
+
+  if out_a == 0 {
+    return Bgra {
+      b: 0,
+      g: 0,
+      r: 0,
+      a: 0,
+    };
+  }
+
+  let blend =
+    |s: u8, d: u8| ((s as u32 * src_a * 255 + d as u32 * dst_a + out_a / 2) / out_a) as u8;
+
+  Bgra {
+    b: blend(src.b, dst.b),
+    g: blend(src.g, dst.g),
+    r: blend(src.r, dst.r),
+    a: ((out_a + 127) / 255) as u8,
+  }
+}
+
+fn over_premultiplied(src: Bgra<u8>, dst: Bgra<u8>) -> Bgra<u8> {
+  let rest = 255 - src.a as u32;
+  let blend = |s: u8, d: u8| (s as u32 + (d as u32 * rest + 127) / 255).min(255) as u8;
+
+  Bgra {
+    b: blend(src.b, dst.b),
+    g: blend(src.g, dst.g),
+    r: blend(src.r, dst.r),
+    a: blend(src.a, dst.a),
+  }
+}
+
+impl FvpHzcEntry<Bgra<u8>> {
+  /// Composite `src` over this image with Porter-Duff "over", placing its top-left corner at
+  /// `(x, y)`. Pixels falling outside of this image are clipped.
+  pub fn composite_over(&mut self, src: &FvpHzcEntry<Bgra<u8>>, x: i32, y: i32, alpha: FvpAlpha) {
+    let over = match alpha {
+      FvpAlpha::Straight => over_straight,
+      FvpAlpha::Premultiplied => over_premultiplied,
+    };
+
+    let (width, height) = (self.data.width() as i64, self.data.height() as i64);
+    let (x, y) = (x as i64, y as i64);
+
+    let left = x.max(0);
+    let top = y.max(0);
+    let right = (x + src.data.width() as i64).min(width);
+    let bottom = (y + src.data.height() as i64).min(height);
+
+    if left >= right || top >= bottom {
+      return;
+    }
+
+    let mut dst = self.data.sub_image_mut(
+      left as usize,
+      top as usize,
+      (right - left) as usize,
+      (bottom - top) as usize,
+    );
+    let src = src.data.sub_image(
+      (left - x) as usize,
+      (top - y) as usize,
+      (right - left) as usize,
+      (bottom - top) as usize,
+    );
+
+    for (dst, src) in dst.rows_mut().zip(src.rows()) {
+      for (dst, src) in dst.iter_mut().zip(src) {
+        *dst = match src.a {
+          0 => *dst,
+          255 => *src,
+          _ => over(*src, *dst),
+        };
+      }
+    }
+  }
+}
+
+impl FvpHzcEntry<Bgr<u8>> {
+  /// Convert to an opaque BGRA image, e.g. to composite layers onto it.
+  pub fn to_bgra(&self) -> FvpHzcEntry<Bgra<u8>> {
+    let buf = self
+      .data
+      .pixels()
+      .map(|Bgr { b, g, r }| Bgra { b, g, r, a: 255 })
+      .collect();
+
+    FvpHzcEntry {
+      offset: self.offset,
+      data: ImgVec::new(buf, self.data.width(), self.data.height()),
+    }
+  }
+}
@@ -0,0 +1,55 @@
+use rgb::Bgra;
+
+use super::composite::FvpAlpha;
+use crate::archive::hzc::FvpHzcEntry;
+
+/// Stack layers, e.g. facial expressions, blush or arm poses, on top of a base image.
+///
+/// The offsets of the layers of a tachie(立ち絵) are relative to the base image, while those of
+/// the differences of an event CG share the origin of the base, i.e. the screen, so they are
+/// added with [`FvpCompositor::add_layer`] and [`FvpCompositor::add_screen_layer`] respectively.
+pub struct FvpCompositor {
+  image: FvpHzcEntry<Bgra<u8>>,
+  alpha: FvpAlpha,
+}
+
+impl FvpCompositor {
+  pub fn new(base: FvpHzcEntry<Bgra<u8>>) -> Self {
+    Self {
+      image: base,
+      alpha: FvpAlpha::default(),
+    }
+  }
+
+  pub fn with_alpha(mut self, alpha: FvpAlpha) -> Self {
+    self.alpha = alpha;
+    self
+  }
+
+  /// Draw `layer` at its offset, which is relative to the base image.
+  pub fn add_layer(&mut self, layer: &FvpHzcEntry<Bgra<u8>>) -> &mut Self {
+    self.add_layer_at(layer, layer.offset.0.into(), layer.offset.1.into())
+  }
+
+  /// Draw `layer` at its offset minus the offset of the base image, as both are positioned on
+  /// the screen.
+  pub fn add_screen_layer(&mut self, layer: &FvpHzcEntry<Bgra<u8>>) -> &mut Self {
+    let x = i32::from(layer.offset.0) - i32::from(self.image.offset.0);
+    let y = i32::from(layer.offset.1) - i32::from(self.image.offset.1);
+    self.add_layer_at(layer, x, y)
+  }
+
+  /// Draw `layer` at `(x, y)` of the base image, ignoring the offsets.
+  pub fn add_layer_at(&mut self, layer: &FvpHzcEntry<Bgra<u8>>, x: i32, y: i32) -> &mut Self {
+    self.image.composite_over(layer, x, y, self.alpha);
+    self
+  }
+
+  pub fn image(&self) -> &FvpHzcEntry<Bgra<u8>> {
+    &self.image
+  }
+
+  pub fn into_image(self) -> FvpHzcEntry<Bgra<u8>> {
+    self.image
+  }
+}
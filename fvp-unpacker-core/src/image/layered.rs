@@ -0,0 +1,270 @@
+use std::io::{Seek, Write};
+
+use imgref::ImgVec;
+use rgb::Bgra;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use super::compositor::FvpCompositor;
+use crate::{archive::hzc::FvpHzcEntry, error::FvpResult};
+
+pub struct FvpLayer {
+  pub name: String,
+  pub image: FvpHzcEntry<Bgra<u8>>,
+  pub x: i32,
+  pub y: i32,
+  pub visible: bool,
+}
+
+/// A document of layers, e.g. a base sprite and its facial expressions, which can be edited in
+/// Krita or GIMP. Layers are stored from the bottom to the top.
+pub struct FvpLayeredImage {
+  width: usize,
+  height: usize,
+  layers: Vec<FvpLayer>,
+}
+
+impl FvpLayeredImage {
+  pub fn new(width: usize, height: usize) -> Self {
+    Self {
+      width,
+      height,
+      layers: Vec::new(),
+    }
+  }
+
+  pub fn add_layer(&mut self, layer: FvpLayer) -> &mut Self {
+    self.layers.push(layer);
+    self
+  }
+
+  pub fn layers(&self) -> &[FvpLayer] {
+    &self.layers
+  }
+
+  /// Flatten the visible layers.
+  pub fn merge(&self) -> FvpHzcEntry<Bgra<u8>> {
+    let canvas = FvpHzcEntry {
+      offset: (0, 0),
+      data: ImgVec::new(
+        vec![Bgra::default(); self.width * self.height],
+        self.width,
+        self.height,
+      ),
+    };
+
+    let mut compositor = FvpCompositor::new(canvas);
+    for layer in self.layers.iter().filter(|layer| layer.visible) {
+      compositor.add_layer_at(&layer.image, layer.x, layer.y);
+    }
+
+    compositor.into_image()
+  }
+
+  /// Write the document in OpenRaster (`.ora`) format.
+  pub fn write_ora<W: Write + Seek>(&self, writer: W) -> FvpResult<()> {
+    fn png(image: &FvpHzcEntry<Bgra<u8>>) -> FvpResult<Vec<u8>> {
+      let mut bytes = Vec::new();
+      image.write_to_png(&mut bytes)?;
+      Ok(bytes)
+    }
+
+    // PNG files are already compressed
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    let mut zip = ZipWriter::new(writer);
+
+    // `mimetype` must be the first file and stored without compression
+    zip.start_file("mimetype", options)?;
+    zip.write_all(b"image/openraster")?;
+
+    let mut stack = String::new();
+    // The first layer in the stack is the top one
+    for (i, layer) in self.layers.iter().enumerate().rev() {
+      stack += &format!(
+        "    <layer name=\"{}\" src=\"data/layer{i}.png\" x=\"{}\" y=\"{}\" visibility=\"{}\" opacity=\"1.0\"/>\n",
+        escape_xml(&layer.name),
+        layer.x,
+        layer.y,
+        if layer.visible { "visible" } else { "hidden" },
+      );
+    }
+
+    zip.start_file("stack.xml", options)?;
+    write!(
+      zip,
+      "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<image version=\"0.0.3\" w=\"{}\" h=\"{}\">\n  <stack>\n{stack}  </stack>\n</image>\n",
+      self.width, self.height,
+    )?;
+
+    for (i, layer) in self.layers.iter().enumerate() {
+      zip.start_file(format!("data/layer{i}.png"), options)?;
+      zip.write_all(&png(&layer.image)?)?;
+    }
+
+    let merged = self.merge();
+
+    zip.start_file("mergedimage.png", options)?;
+    zip.write_all(&png(&merged)?)?;
+
+    zip.start_file("Thumbnails/thumbnail.png", options)?;
+    zip.write_all(&png(&thumbnail(&merged, 256))?)?;
+
+    zip.finish()?;
+
+    Ok(())
+  }
+
+  /// Write the document in Photoshop (`.psd`) format, with uncompressed 8-bit RGBA layers.
+  pub fn write_psd<W: Write>(&self, mut writer: W) -> FvpResult<()> {
+    fn channels(image: &FvpHzcEntry<Bgra<u8>>) -> [(i16, Vec<u8>); 4] {
+      let pixels: Vec<_> = image.data.pixels().collect();
+      [
+        (-1, pixels.iter().map(|pixel| pixel.a).collect()),
+        (0, pixels.iter().map(|pixel| pixel.r).collect()),
+        (1, pixels.iter().map(|pixel| pixel.g).collect()),
+        (2, pixels.iter().map(|pixel| pixel.b).collect()),
+      ]
+    }
+
+    let mut layer_info = Vec::new();
+    layer_info.extend_from_slice(&(self.layers.len() as i16).to_be_bytes());
+
+    let layer_channels: Vec<_> = self
+      .layers
+      .iter()
+      .map(|layer| channels(&layer.image))
+      .collect();
+
+    for (layer, channels) in self.layers.iter().zip(&layer_channels) {
+      let (width, height) = (layer.image.data.width(), layer.image.data.height());
+
+      for x in [
+        layer.y,
+        layer.x,
+        layer.y + height as i32,
+        layer.x + width as i32,
+      ] {
+        layer_info.extend_from_slice(&x.to_be_bytes());
+      }
+
+      layer_info.extend_from_slice(&(channels.len() as u16).to_be_bytes());
+      for (id, data) in channels {
+        layer_info.extend_from_slice(&id.to_be_bytes());
+        layer_info.extend_from_slice(&(data.len() as u32 + 2).to_be_bytes());
+      }
+
+      layer_info.extend_from_slice(b"8BIMnorm");
+      // opacity, clipping, flags (bit 1 means hidden) and filler
+      layer_info.extend_from_slice(&[255, 0, if layer.visible { 0 } else { 2 }, 0]);
+
+      let mut extra = Vec::new();
+      // layer mask and blending ranges
+      extra.extend_from_slice(&0u32.to_be_bytes());
+      extra.extend_from_slice(&0u32.to_be_bytes());
+
+      let ascii_name: Vec<u8> = layer
+        .name
+        .chars()
+        .take(255)
+        .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+        .collect();
+      extra.push(ascii_name.len() as u8);
+      extra.extend_from_slice(&ascii_name);
+      while extra.len() % 4 != 0 {
+        extra.push(0);
+      }
+
+      // Unicode layer name
+      let mut luni = Vec::new();
+      let utf16: Vec<u16> = layer.name.encode_utf16().collect();
+      luni.extend_from_slice(&(utf16.len() as u32).to_be_bytes());
+      for c in utf16 {
+        luni.extend_from_slice(&c.to_be_bytes());
+      }
+      while luni.len() % 4 != 0 {
+        luni.push(0);
+      }
+      extra.extend_from_slice(b"8BIMluni");
+      extra.extend_from_slice(&(luni.len() as u32).to_be_bytes());
+      extra.extend_from_slice(&luni);
+
+      layer_info.extend_from_slice(&(extra.len() as u32).to_be_bytes());
+      layer_info.extend_from_slice(&extra);
+    }
+
+    for channels in &layer_channels {
+      for (_, data) in channels {
+        // raw data without compression
+        layer_info.extend_from_slice(&0u16.to_be_bytes());
+        layer_info.extend_from_slice(data);
+      }
+    }
+
+    if layer_info.len() % 2 != 0 {
+      layer_info.push(0);
+    }
+
+    writer.write_all(b"8BPS")?;
+    writer.write_all(&1u16.to_be_bytes())?;
+    writer.write_all(&[0; 6])?;
+    writer.write_all(&4u16.to_be_bytes())?;
+    writer.write_all(&(self.height as u32).to_be_bytes())?;
+    writer.write_all(&(self.width as u32).to_be_bytes())?;
+    writer.write_all(&8u16.to_be_bytes())?;
+    // RGB color mode
+    writer.write_all(&3u16.to_be_bytes())?;
+
+    // color mode data and image resources
+    writer.write_all(&0u32.to_be_bytes())?;
+    writer.write_all(&0u32.to_be_bytes())?;
+
+    // layer and mask information, with an empty global layer mask
+    writer.write_all(&(layer_info.len() as u32 + 8).to_be_bytes())?;
+    writer.write_all(&(layer_info.len() as u32).to_be_bytes())?;
+    writer.write_all(&layer_info)?;
+    writer.write_all(&0u32.to_be_bytes())?;
+
+    // merged image data, which is planar in the order of R, G, B and A
+    let [alpha, red, green, blue] = channels(&self.merge());
+    writer.write_all(&0u16.to_be_bytes())?;
+    for (_, data) in [red, green, blue, alpha] {
+      writer.write_all(&data)?;
+    }
+
+    Ok(())
+  }
+}
+
+fn escape_xml(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+/// Scale the image down with nearest-neighbor to fit in `size` x `size`.
+fn thumbnail(image: &FvpHzcEntry<Bgra<u8>>, size: usize) -> FvpHzcEntry<Bgra<u8>> {
+  let (width, height) = (image.data.width().max(1), image.data.height().max(1));
+  let scale = (size as f64 / width.max(height) as f64).min(1.0);
+  let (thumb_width, thumb_height) = (
+    ((width as f64 * scale) as usize).max(1),
+    ((height as f64 * scale) as usize).max(1),
+  );
+
+  let buf = (0..thumb_height)
+    .flat_map(|y| {
+      (0..thumb_width).map(move |x| {
+        image.data[(
+          (x * width / thumb_width).min(image.data.width().saturating_sub(1)),
+          (y * height / thumb_height).min(image.data.height().saturating_sub(1)),
+        )]
+      })
+    })
+    .collect();
+
+  FvpHzcEntry {
+    offset: (0, 0),
+    data: ImgVec::new(buf, thumb_width, thumb_height),
+  }
+}
@@ -0,0 +1,9 @@
+pub mod animation;
+pub mod atlas;
+pub mod canvas;
+pub mod cg;
+pub mod composite;
+pub mod compositor;
+pub mod layered;
+pub mod tachie;
+pub mod trim;
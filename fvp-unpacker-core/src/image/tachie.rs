@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use crate::archive::{bin::FvpBin, hzc::DynamicFvpHzc};
+
+/// The suffix of the sheet containing the facial expressions of a tachie(立ち絵)
+pub const FACIAL_EXPRESSION_SUFFIX: &str = "_表情";
+
+/// A tachie(立ち絵) named like `CHR_雪々_喜_着物U`, i.e. the character, pose and outfit separated
+/// by `_` after the prefix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FvpTachie {
+  pub name: String,
+  pub character: String,
+  pub pose: Option<String>,
+  pub outfit: Option<String>,
+  pub width: u16,
+  pub height: u16,
+  /// Number of frames in the facial expression sheet, if any
+  pub facial_expressions: Option<u32>,
+}
+
+impl FvpBin {
+  /// Find every image whose name starts with `prefix`, except the facial expression sheets which
+  /// are counted for their tachie instead. Only the headers are parsed.
+  pub fn tachies(&self, prefix: &str) -> Vec<FvpTachie> {
+    let headers: HashMap<_, _> = self
+      .entries()
+      .iter()
+      .filter(|entry| entry.filename().starts_with(prefix))
+      .filter_map(|entry| {
+        let header = DynamicFvpHzc::parse_header(entry.data()).ok()?;
+        Some((entry.filename(), header))
+      })
+      .collect();
+
+    let mut tachies: Vec<_> = headers
+      .iter()
+      .filter(|(name, _)| !name.ends_with(FACIAL_EXPRESSION_SUFFIX))
+      .map(|(&name, header)| {
+        let mut parts = name[prefix.len()..].splitn(3, '_');
+
+        FvpTachie {
+          name: name.to_string(),
+          character: parts.next().unwrap_or_default().to_string(),
+          pose: parts.next().map(str::to_string),
+          outfit: parts.next().map(str::to_string),
+          width: header.width,
+          height: header.height,
+          facial_expressions: headers
+            .get(format!("{name}{FACIAL_EXPRESSION_SUFFIX}").as_str())
+            .map(|sheet| sheet.count),
+        }
+      })
+      .collect();
+
+    tachies.sort_by(|a, b| a.name.cmp(&b.name));
+
+    tachies
+  }
+}
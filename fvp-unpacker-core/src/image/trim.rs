@@ -0,0 +1,82 @@
+use imgref::ImgVec;
+use rgb::Bgra;
+
+use crate::archive::hzc::FvpHzcEntry;
+
+/// Where the trimmed image was cropped from the original one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FvpTrim {
+  pub x: usize,
+  pub y: usize,
+  pub width: usize,
+  pub height: usize,
+  pub source_width: usize,
+  pub source_height: usize,
+}
+
+impl FvpHzcEntry<Bgra<u8>> {
+  /// The bounding box of the pixels which are not fully transparent.
+  pub fn alpha_bounds(&self) -> Option<FvpTrim> {
+    // `rows` panics on an empty buffer
+    if self.data.width() == 0 || self.data.height() == 0 {
+      return None;
+    }
+
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+
+    for (y, row) in self.data.rows().enumerate() {
+      let Some(left) = row.iter().position(|pixel| pixel.a != 0) else {
+        continue;
+      };
+      let right = row.iter().rposition(|pixel| pixel.a != 0).unwrap_or(left);
+
+      bounds = Some(match bounds {
+        Some((x0, y0, x1, _)) => (x0.min(left), y0, x1.max(right), y),
+        None => (left, y, right, y),
+      });
+    }
+
+    bounds.map(|(x0, y0, x1, y1)| FvpTrim {
+      x: x0,
+      y: y0,
+      width: x1 - x0 + 1,
+      height: y1 - y0 + 1,
+      source_width: self.data.width(),
+      source_height: self.data.height(),
+    })
+  }
+
+  /// Crop the transparent borders. The offset of the trimmed image is moved by the removed left and
+  /// top borders, so it can still be placed at the same position. A fully transparent image is
+  /// trimmed to a single pixel, and an empty one is returned as it is.
+  pub fn trim(&self) -> (FvpHzcEntry<Bgra<u8>>, FvpTrim) {
+    let trim = self.alpha_bounds().unwrap_or(FvpTrim {
+      x: 0,
+      y: 0,
+      width: self.data.width().min(1),
+      height: self.data.height().min(1),
+      source_width: self.data.width(),
+      source_height: self.data.height(),
+    });
+
+    if trim.width == 0 || trim.height == 0 {
+      return (self.clone(), trim);
+    }
+
+    let (buf, width, height) = self
+      .data
+      .sub_image(trim.x, trim.y, trim.width, trim.height)
+      .to_contiguous_buf();
+
+    let image = FvpHzcEntry {
+      offset: (
+        (self.offset.0 as usize + trim.x).min(u16::MAX.into()) as u16,
+        (self.offset.1 as usize + trim.y).min(u16::MAX.into()) as u16,
+      ),
+      data: ImgVec::new(buf.into_owned(), width, height),
+    };
+
+    (image, trim)
+  }
+}
@@ -1,4 +1,7 @@
 pub mod archive;
+pub mod audio;
 pub mod error;
+pub mod image;
 pub mod prelude;
+pub mod script;
 pub mod utils;
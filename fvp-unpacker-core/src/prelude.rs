@@ -1,3 +1,6 @@
 pub use crate::archive::bin::{FvpBin, FvpBinEntry};
 pub use crate::archive::hzc::DynamicFvpHzc;
 pub use crate::error::FvpError;
+pub use crate::image::composite::FvpAlpha;
+pub use crate::image::compositor::FvpCompositor;
+pub use crate::script::hcb::FvpHcb;
@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+
+use super::{
+  decompile::{FvpDecompiledScript, FvpExpr},
+  entry_name,
+};
+use crate::archive::bin::{FvpBin, FvpBinEntry};
+
+/// Syscalls loading images by default, e.g. `GraphLoad(slot, "graph/bg001")`.
+pub const DEFAULT_IMAGE_SYSCALLS: &[&str] = &["GraphLoad"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FvpAssetReference {
+  /// Address of the function where the asset is referenced
+  pub function: u32,
+  pub syscall: String,
+  /// Asset path as referenced by the script
+  pub path: String,
+  /// Constant integer arguments of the syscall, e.g. slot or frame indices
+  pub indices: Vec<i32>,
+}
+
+impl FvpAssetReference {
+  /// The entry name in the archive, i.e. the asset path without any directories.
+  pub fn entry_name(&self) -> &str {
+    entry_name(&self.path)
+  }
+}
+
+impl FvpDecompiledScript<'_> {
+  /// Collect the string arguments of the given syscalls, in the order they are called in each
+  /// function.
+  pub fn asset_references(&self, syscalls: &[impl AsRef<str>]) -> Vec<FvpAssetReference> {
+    let hcb = self.hcb();
+    let mut references = Vec::new();
+
+    for function in self.functions() {
+      let mut visit = |expr: &FvpExpr| {
+        let FvpExpr::Syscall(id, args) = expr else {
+          return;
+        };
+
+        let name = hcb.syscall_name(*id);
+
+        if !syscalls.iter().any(|x| x.as_ref() == name) {
+          return;
+        }
+
+        let indices: Vec<_> = args
+          .iter()
+          .filter_map(|arg| match arg {
+            FvpExpr::Int(x) => Some(*x),
+            _ => None,
+          })
+          .collect();
+
+        for arg in args {
+          if let FvpExpr::Str(path) = arg {
+            references.push(FvpAssetReference {
+              function: function.address,
+              syscall: name.to_string(),
+              path: path.clone(),
+              indices: indices.clone(),
+            });
+          }
+        }
+      };
+
+      for stmt in &function.body {
+        stmt.walk_exprs(&mut visit);
+      }
+    }
+
+    references
+  }
+}
+
+/// The entries of `arc` which are never referenced, in the order of the archive.
+pub fn unreferenced_entries<'a>(
+  arc: &'a FvpBin,
+  references: &[FvpAssetReference],
+) -> Vec<&'a FvpBinEntry> {
+  let referenced: HashSet<_> = references
+    .iter()
+    .map(FvpAssetReference::entry_name)
+    .collect();
+
+  arc
+    .entries()
+    .iter()
+    .filter(|entry| !referenced.contains(entry.filename()))
+    .collect()
+}
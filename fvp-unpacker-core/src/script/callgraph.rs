@@ -0,0 +1,157 @@
+use std::{collections::BTreeSet, io::Write};
+
+use super::decompile::{FvpDecompiledScript, FvpExpr, FvpStmt, function_name};
+use crate::error::FvpResult;
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FvpCallGraphNode {
+  pub address: u32,
+  pub name: String,
+  /// Addresses of the functions called by this function
+  pub calls: BTreeSet<u32>,
+  /// Names of the syscalls called by this function
+  pub syscalls: BTreeSet<String>,
+  /// Global variables (flags) read by this function
+  pub reads: BTreeSet<u16>,
+  /// Global variables (flags) written by this function
+  pub writes: BTreeSet<u16>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FvpCallGraph {
+  pub entry_point: u32,
+  pub functions: Vec<FvpCallGraphNode>,
+}
+
+impl FvpDecompiledScript<'_> {
+  pub fn call_graph(&self) -> FvpCallGraph {
+    let hcb = self.hcb();
+
+    let functions = self
+      .functions()
+      .iter()
+      .map(|function| {
+        let mut node = FvpCallGraphNode {
+          address: function.address,
+          name: function_name(function.address),
+          ..Default::default()
+        };
+
+        let mut read = |expr: &FvpExpr| match expr {
+          FvpExpr::Global(id) | FvpExpr::GlobalTable(id, _) => {
+            node.reads.insert(*id);
+          }
+          FvpExpr::Call(address, _) => {
+            node.calls.insert(*address);
+          }
+          FvpExpr::Syscall(id, _) => {
+            node.syscalls.insert(hcb.syscall_name(*id).into_owned());
+          }
+          _ => {}
+        };
+
+        let mut writes = BTreeSet::new();
+        visit_block(&function.body, &mut read, &mut writes);
+        node.writes = writes;
+
+        node
+      })
+      .collect();
+
+    FvpCallGraph {
+      entry_point: hcb.entry_point(),
+      functions,
+    }
+  }
+}
+
+fn visit_block(stmts: &[FvpStmt], read: &mut impl FnMut(&FvpExpr), writes: &mut BTreeSet<u16>) {
+  for stmt in stmts {
+    match stmt {
+      FvpStmt::Assign(dst, value) => {
+        value.walk(read);
+
+        match dst {
+          FvpExpr::Global(id) => {
+            writes.insert(*id);
+          }
+          FvpExpr::GlobalTable(id, key) => {
+            key.walk(read);
+            writes.insert(*id);
+          }
+          FvpExpr::LocalTable(_, key) => key.walk(read),
+          _ => {}
+        }
+      }
+      FvpStmt::If {
+        cond,
+        then,
+        otherwise,
+      } => {
+        cond.walk(read);
+        visit_block(then, read, writes);
+        visit_block(otherwise, read, writes);
+      }
+      FvpStmt::While { cond, body } => {
+        cond.walk(read);
+        visit_block(body, read, writes);
+      }
+      stmt => stmt.walk_exprs(read),
+    }
+  }
+}
+
+impl FvpCallGraph {
+  /// Write the call graph in Graphviz DOT format.
+  pub fn write_dot<W: Write>(&self, mut writer: W) -> FvpResult<()> {
+    fn join(ids: &BTreeSet<u16>) -> String {
+      ids
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
+    writeln!(writer, "digraph hcb {{")?;
+    writeln!(writer, "  node [shape=box];")?;
+
+    for function in &self.functions {
+      let mut label = function.name.clone();
+      if !function.reads.is_empty() {
+        label += &format!("\\nreads: {}", join(&function.reads));
+      }
+      if !function.writes.is_empty() {
+        label += &format!("\\nwrites: {}", join(&function.writes));
+      }
+
+      let peripheries = if function.address == self.entry_point {
+        2
+      } else {
+        1
+      };
+
+      writeln!(
+        writer,
+        "  \"{}\" [label=\"{label}\", peripheries={peripheries}];",
+        function.name,
+      )?;
+    }
+
+    for function in &self.functions {
+      for callee in &function.calls {
+        writeln!(
+          writer,
+          "  \"{}\" -> \"{}\";",
+          function.name,
+          function_name(*callee),
+        )?;
+      }
+    }
+
+    writeln!(writer, "}}")?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,603 @@
+use std::{
+  collections::{HashMap, HashSet},
+  fmt,
+};
+
+use super::hcb::{FvpHcb, FvpHcbFunction, FvpHcbInstruction, FvpHcbOpcode};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FvpUnaryOp {
+  Neg,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FvpBinaryOp {
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Mod,
+  BitTest,
+  And,
+  Or,
+  Eq,
+  Ne,
+  Gt,
+  Le,
+  Lt,
+  Ge,
+}
+
+impl FvpBinaryOp {
+  fn symbol(self) -> &'static str {
+    match self {
+      Self::Add => "+",
+      Self::Sub => "-",
+      Self::Mul => "*",
+      Self::Div => "/",
+      Self::Mod => "%",
+      Self::BitTest => "bittest",
+      Self::And => "&&",
+      Self::Or => "||",
+      Self::Eq => "==",
+      Self::Ne => "!=",
+      Self::Gt => ">",
+      Self::Le => "<=",
+      Self::Lt => "<",
+      Self::Ge => ">=",
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FvpExpr {
+  Nil,
+  True,
+  Int(i32),
+  Float(f32),
+  Str(String),
+  Global(u16),
+  Local(i8),
+  GlobalTable(u16, Box<FvpExpr>),
+  LocalTable(i8, Box<FvpExpr>),
+  Unary(FvpUnaryOp, Box<FvpExpr>),
+  Binary(FvpBinaryOp, Box<FvpExpr>, Box<FvpExpr>),
+  Call(u32, Vec<FvpExpr>),
+  Syscall(u16, Vec<FvpExpr>),
+  /// `push_return` without a preceding call
+  ReturnValue,
+  /// The value popped from an empty stack
+  Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FvpStmt {
+  Expr(FvpExpr),
+  Assign(FvpExpr, FvpExpr),
+  If {
+    cond: FvpExpr,
+    then: Vec<FvpStmt>,
+    otherwise: Vec<FvpStmt>,
+  },
+  While {
+    cond: FvpExpr,
+    body: Vec<FvpStmt>,
+  },
+  Return(Option<FvpExpr>),
+  Label(u32),
+  Goto(u32),
+  GotoUnless(FvpExpr, u32),
+}
+
+impl FvpExpr {
+  /// Visit this expression and all of its sub-expressions, operands first.
+  pub fn walk(&self, f: &mut impl FnMut(&FvpExpr)) {
+    match self {
+      Self::GlobalTable(_, key) | Self::LocalTable(_, key) => key.walk(f),
+      Self::Unary(_, value) => value.walk(f),
+      Self::Binary(_, lhs, rhs) => {
+        lhs.walk(f);
+        rhs.walk(f);
+      }
+      Self::Call(_, args) | Self::Syscall(_, args) => args.iter().for_each(|arg| arg.walk(f)),
+      _ => {}
+    }
+
+    f(self);
+  }
+}
+
+impl FvpStmt {
+  /// Visit every expression in this statement and its nested blocks in execution order.
+  pub fn walk_exprs(&self, f: &mut impl FnMut(&FvpExpr)) {
+    match self {
+      Self::Expr(expr) | Self::GotoUnless(expr, _) | Self::Return(Some(expr)) => expr.walk(f),
+      Self::Assign(dst, value) => {
+        value.walk(f);
+        dst.walk(f);
+      }
+      Self::If {
+        cond,
+        then,
+        otherwise,
+      } => {
+        cond.walk(f);
+        then.iter().for_each(|stmt| stmt.walk_exprs(f));
+        otherwise.iter().for_each(|stmt| stmt.walk_exprs(f));
+      }
+      Self::While { cond, body } => {
+        cond.walk(f);
+        body.iter().for_each(|stmt| stmt.walk_exprs(f));
+      }
+      Self::Return(None) | Self::Label(_) | Self::Goto(_) => {}
+    }
+  }
+}
+
+pub struct FvpDecompiledFunction {
+  pub address: u32,
+  pub args: u8,
+  pub locals: u8,
+  pub body: Vec<FvpStmt>,
+}
+
+pub struct FvpDecompiledScript<'a> {
+  hcb: &'a FvpHcb,
+  functions: Vec<FvpDecompiledFunction>,
+}
+
+impl FvpDecompiledScript<'_> {
+  pub fn hcb(&self) -> &FvpHcb {
+    self.hcb
+  }
+
+  pub fn functions(&self) -> &[FvpDecompiledFunction] {
+    &self.functions
+  }
+}
+
+impl FvpHcb {
+  /// Rebuild expressions and control flow of every function from the stack machine bytecode.
+  pub fn decompile(&self) -> FvpDecompiledScript<'_> {
+    let functions = self.functions();
+
+    let function_args: HashMap<_, _> = functions
+      .iter()
+      .map(|function| (function.address, function.args))
+      .collect();
+
+    let functions = functions
+      .iter()
+      .map(|function| decompile_function(self, &function_args, function))
+      .collect();
+
+    FvpDecompiledScript {
+      hcb: self,
+      functions,
+    }
+  }
+}
+
+fn decompile_function(
+  hcb: &FvpHcb,
+  function_args: &HashMap<u32, u8>,
+  function: &FvpHcbFunction,
+) -> FvpDecompiledFunction {
+  let mut decompiler = FunctionDecompiler {
+    hcb,
+    function_args,
+    instructions: function.instructions,
+    labels: HashSet::new(),
+    goto_targets: HashSet::new(),
+  };
+
+  // The first pass finds out which jumps can not be structured, and the second one places labels
+  // for them.
+  decompiler.block(0, function.instructions.len());
+  decompiler.labels = std::mem::take(&mut decompiler.goto_targets);
+  let body = decompiler.block(0, function.instructions.len());
+
+  FvpDecompiledFunction {
+    address: function.address,
+    args: function.args,
+    locals: function.locals,
+    body,
+  }
+}
+
+struct FunctionDecompiler<'a> {
+  hcb: &'a FvpHcb,
+  function_args: &'a HashMap<u32, u8>,
+  instructions: &'a [FvpHcbInstruction],
+  labels: HashSet<u32>,
+  goto_targets: HashSet<u32>,
+}
+
+impl FunctionDecompiler<'_> {
+  fn index_of(&self, address: u32) -> Option<usize> {
+    self
+      .instructions
+      .binary_search_by_key(&address, |instruction| instruction.address)
+      .ok()
+  }
+
+  fn block(&mut self, start: usize, end: usize) -> Vec<FvpStmt> {
+    fn pop(stack: &mut Vec<FvpExpr>) -> FvpExpr {
+      stack.pop().unwrap_or(FvpExpr::Unknown)
+    }
+
+    fn pop_args(stack: &mut Vec<FvpExpr>, count: usize) -> Vec<FvpExpr> {
+      let mut args: Vec<_> = (0..count).map(|_| pop(stack)).collect();
+      args.reverse();
+      args
+    }
+
+    fn flush(stack: &mut Vec<FvpExpr>, stmts: &mut Vec<FvpStmt>) {
+      stmts.extend(stack.drain(..).map(FvpStmt::Expr));
+    }
+
+    let mut stmts = Vec::new();
+    let mut stack = Vec::new();
+    let mut stmt_start = start;
+    let mut i = start;
+
+    while i < end {
+      let instruction = &self.instructions[i];
+
+      if self.labels.contains(&instruction.address) {
+        stmts.push(FvpStmt::Label(instruction.address));
+      }
+
+      if stack.is_empty() {
+        stmt_start = i;
+      }
+
+      match &instruction.opcode {
+        FvpHcbOpcode::Nop | FvpHcbOpcode::InitStack { .. } => {}
+        FvpHcbOpcode::Call(_) | FvpHcbOpcode::Syscall(_) => {
+          let expr = match instruction.opcode {
+            FvpHcbOpcode::Call(address) => {
+              let count = self.function_args.get(&address).copied().unwrap_or(0);
+              FvpExpr::Call(address, pop_args(&mut stack, count as usize))
+            }
+            FvpHcbOpcode::Syscall(id) => {
+              let count = self
+                .hcb
+                .syscalls()
+                .get(id as usize)
+                .map_or(0, |syscall| syscall.args);
+              FvpExpr::Syscall(id, pop_args(&mut stack, count as usize))
+            }
+            _ => unreachable!(),
+          };
+
+          let used =
+            i + 1 < end && matches!(self.instructions[i + 1].opcode, FvpHcbOpcode::PushReturn);
+
+          if used {
+            stack.push(expr);
+            i += 1;
+          } else {
+            flush(&mut stack, &mut stmts);
+            stmts.push(FvpStmt::Expr(expr));
+          }
+        }
+        FvpHcbOpcode::Ret => {
+          flush(&mut stack, &mut stmts);
+          stmts.push(FvpStmt::Return(None));
+        }
+        FvpHcbOpcode::RetV => {
+          let value = pop(&mut stack);
+          flush(&mut stack, &mut stmts);
+          stmts.push(FvpStmt::Return(Some(value)));
+        }
+        FvpHcbOpcode::Jmp(target) => {
+          flush(&mut stack, &mut stmts);
+          stmts.push(FvpStmt::Goto(*target));
+          self.goto_targets.insert(*target);
+        }
+        FvpHcbOpcode::Jz(target) => {
+          let cond = pop(&mut stack);
+          flush(&mut stack, &mut stmts);
+
+          match self.index_of(*target) {
+            Some(t) if t > i && t <= end => {
+              let jump_back = match self.instructions[t - 1].opcode {
+                FvpHcbOpcode::Jmp(address) if t - 1 > i => self.index_of(address),
+                _ => None,
+              };
+
+              match jump_back {
+                Some(header) if header == stmt_start => {
+                  let body = self.block(i + 1, t - 1);
+                  stmts.push(FvpStmt::While { cond, body });
+                  i = t;
+                }
+                Some(merge) if merge >= t && merge <= end => {
+                  let then = self.block(i + 1, t - 1);
+                  let otherwise = self.block(t, merge);
+                  stmts.push(FvpStmt::If {
+                    cond,
+                    then,
+                    otherwise,
+                  });
+                  i = merge;
+                }
+                _ => {
+                  let then = self.block(i + 1, t);
+                  stmts.push(FvpStmt::If {
+                    cond,
+                    then,
+                    otherwise: Vec::new(),
+                  });
+                  i = t;
+                }
+              }
+
+              continue;
+            }
+            _ => {
+              stmts.push(FvpStmt::GotoUnless(cond, *target));
+              self.goto_targets.insert(*target);
+            }
+          }
+        }
+        FvpHcbOpcode::PushNil => stack.push(FvpExpr::Nil),
+        FvpHcbOpcode::PushTrue => stack.push(FvpExpr::True),
+        FvpHcbOpcode::PushI32(x) => stack.push(FvpExpr::Int(*x)),
+        FvpHcbOpcode::PushI16(x) => stack.push(FvpExpr::Int((*x).into())),
+        FvpHcbOpcode::PushI8(x) => stack.push(FvpExpr::Int((*x).into())),
+        FvpHcbOpcode::PushF32(x) => stack.push(FvpExpr::Float(*x)),
+        FvpHcbOpcode::PushString(x) => stack.push(FvpExpr::Str(x.clone())),
+        FvpHcbOpcode::PushGlobal(id) => stack.push(FvpExpr::Global(*id)),
+        FvpHcbOpcode::PushStack(idx) => stack.push(FvpExpr::Local(*idx)),
+        FvpHcbOpcode::PushGlobalTable(id) => {
+          let key = pop(&mut stack);
+          stack.push(FvpExpr::GlobalTable(*id, Box::new(key)));
+        }
+        FvpHcbOpcode::PushLocalTable(idx) => {
+          let key = pop(&mut stack);
+          stack.push(FvpExpr::LocalTable(*idx, Box::new(key)));
+        }
+        FvpHcbOpcode::PushTop => {
+          let top = stack.last().cloned().unwrap_or(FvpExpr::Unknown);
+          stack.push(top);
+        }
+        FvpHcbOpcode::PushReturn => stack.push(FvpExpr::ReturnValue),
+        FvpHcbOpcode::PopGlobal(id) => {
+          let value = pop(&mut stack);
+          stmts.push(FvpStmt::Assign(FvpExpr::Global(*id), value));
+        }
+        FvpHcbOpcode::PopStack(idx) => {
+          let value = pop(&mut stack);
+          stmts.push(FvpStmt::Assign(FvpExpr::Local(*idx), value));
+        }
+        FvpHcbOpcode::PopGlobalTable(id) => {
+          let value = pop(&mut stack);
+          let key = pop(&mut stack);
+          stmts.push(FvpStmt::Assign(
+            FvpExpr::GlobalTable(*id, Box::new(key)),
+            value,
+          ));
+        }
+        FvpHcbOpcode::PopLocalTable(idx) => {
+          let value = pop(&mut stack);
+          let key = pop(&mut stack);
+          stmts.push(FvpStmt::Assign(
+            FvpExpr::LocalTable(*idx, Box::new(key)),
+            value,
+          ));
+        }
+        FvpHcbOpcode::Neg => {
+          let value = pop(&mut stack);
+          stack.push(FvpExpr::Unary(FvpUnaryOp::Neg, Box::new(value)));
+        }
+        opcode => {
+          let op = match opcode {
+            FvpHcbOpcode::Add => FvpBinaryOp::Add,
+            FvpHcbOpcode::Sub => FvpBinaryOp::Sub,
+            FvpHcbOpcode::Mul => FvpBinaryOp::Mul,
+            FvpHcbOpcode::Div => FvpBinaryOp::Div,
+            FvpHcbOpcode::Mod => FvpBinaryOp::Mod,
+            FvpHcbOpcode::BitTest => FvpBinaryOp::BitTest,
+            FvpHcbOpcode::And => FvpBinaryOp::And,
+            FvpHcbOpcode::Or => FvpBinaryOp::Or,
+            FvpHcbOpcode::SetE => FvpBinaryOp::Eq,
+            FvpHcbOpcode::SetNE => FvpBinaryOp::Ne,
+            FvpHcbOpcode::SetG => FvpBinaryOp::Gt,
+            FvpHcbOpcode::SetLE => FvpBinaryOp::Le,
+            FvpHcbOpcode::SetL => FvpBinaryOp::Lt,
+            FvpHcbOpcode::SetGE => FvpBinaryOp::Ge,
+            _ => unreachable!(),
+          };
+
+          let rhs = pop(&mut stack);
+          let lhs = pop(&mut stack);
+          stack.push(FvpExpr::Binary(op, Box::new(lhs), Box::new(rhs)));
+        }
+      }
+
+      i += 1;
+    }
+
+    flush(&mut stack, &mut stmts);
+    stmts
+  }
+}
+
+pub fn function_name(address: u32) -> String {
+  format!("sub_{address:08x}")
+}
+
+struct ExprPrinter<'a> {
+  hcb: &'a FvpHcb,
+  expr: &'a FvpExpr,
+}
+
+impl fmt::Display for ExprPrinter<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let hcb = self.hcb;
+    let print = |expr| ExprPrinter { hcb, expr };
+
+    let write_args = |f: &mut fmt::Formatter<'_>, args: &[FvpExpr]| {
+      for (i, arg) in args.iter().enumerate() {
+        if i != 0 {
+          write!(f, ", ")?;
+        }
+        write!(f, "{}", ExprPrinter { hcb, expr: arg })?;
+      }
+      Ok(())
+    };
+
+    match self.expr {
+      FvpExpr::Nil => write!(f, "nil"),
+      FvpExpr::True => write!(f, "true"),
+      FvpExpr::Int(x) => write!(f, "{x}"),
+      FvpExpr::Float(x) => write!(f, "{x:?}"),
+      FvpExpr::Str(x) => write!(f, "{x:?}"),
+      FvpExpr::Global(id) => write!(f, "global[{id}]"),
+      FvpExpr::Local(idx) => write!(f, "local[{idx}]"),
+      FvpExpr::GlobalTable(id, key) => write!(f, "global[{id}][{}]", print(key)),
+      FvpExpr::LocalTable(idx, key) => write!(f, "local[{idx}][{}]", print(key)),
+      FvpExpr::Unary(FvpUnaryOp::Neg, value) => match value.as_ref() {
+        FvpExpr::Binary(..) => write!(f, "-({})", print(value)),
+        _ => write!(f, "-{}", print(value)),
+      },
+      FvpExpr::Binary(FvpBinaryOp::BitTest, lhs, rhs) => {
+        write!(f, "bittest({}, {})", print(lhs), print(rhs))
+      }
+      FvpExpr::Binary(op, lhs, rhs) => {
+        for (i, operand) in [lhs, rhs].into_iter().enumerate() {
+          if i != 0 {
+            write!(f, " {} ", op.symbol())?;
+          }
+          match operand.as_ref() {
+            FvpExpr::Binary(op, ..) if *op != FvpBinaryOp::BitTest => {
+              write!(f, "({})", print(operand))?
+            }
+            _ => write!(f, "{}", print(operand))?,
+          }
+        }
+        Ok(())
+      }
+      FvpExpr::Call(address, args) => {
+        write!(f, "{}(", function_name(*address))?;
+        write_args(f, args)?;
+        write!(f, ")")
+      }
+      FvpExpr::Syscall(id, args) => {
+        write!(f, "{}(", hcb.syscall_name(*id))?;
+        write_args(f, args)?;
+        write!(f, ")")
+      }
+      FvpExpr::ReturnValue => write!(f, "$return"),
+      FvpExpr::Unknown => write!(f, "?"),
+    }
+  }
+}
+
+fn write_block(
+  f: &mut fmt::Formatter<'_>,
+  hcb: &FvpHcb,
+  stmts: &[FvpStmt],
+  depth: usize,
+) -> fmt::Result {
+  let print = |expr| ExprPrinter { hcb, expr };
+  let indent = "  ".repeat(depth);
+
+  for stmt in stmts {
+    match stmt {
+      FvpStmt::Expr(expr) => writeln!(f, "{indent}{};", print(expr))?,
+      FvpStmt::Assign(dst, value) => writeln!(f, "{indent}{} = {};", print(dst), print(value))?,
+      FvpStmt::If {
+        cond,
+        then,
+        otherwise,
+      } => {
+        write!(f, "{indent}")?;
+        write_if(f, hcb, cond, then, otherwise, depth)?;
+      }
+      FvpStmt::While { cond, body } => {
+        writeln!(f, "{indent}while ({}) {{", print(cond))?;
+        write_block(f, hcb, body, depth + 1)?;
+        writeln!(f, "{indent}}}")?;
+      }
+      FvpStmt::Return(None) => writeln!(f, "{indent}return;")?,
+      FvpStmt::Return(Some(value)) => writeln!(f, "{indent}return {};", print(value))?,
+      FvpStmt::Label(address) => writeln!(f, "label_{address:08x}:")?,
+      FvpStmt::Goto(address) => writeln!(f, "{indent}goto label_{address:08x};")?,
+      FvpStmt::GotoUnless(cond, address) => writeln!(
+        f,
+        "{indent}if (!({})) goto label_{address:08x};",
+        print(cond)
+      )?,
+    }
+  }
+
+  Ok(())
+}
+
+fn write_if(
+  f: &mut fmt::Formatter<'_>,
+  hcb: &FvpHcb,
+  cond: &FvpExpr,
+  then: &[FvpStmt],
+  otherwise: &[FvpStmt],
+  depth: usize,
+) -> fmt::Result {
+  let indent = "  ".repeat(depth);
+
+  writeln!(f, "if ({}) {{", ExprPrinter { hcb, expr: cond })?;
+  write_block(f, hcb, then, depth + 1)?;
+
+  match otherwise {
+    [] => writeln!(f, "{indent}}}"),
+    [
+      FvpStmt::If {
+        cond,
+        then,
+        otherwise,
+      },
+    ] => {
+      write!(f, "{indent}}} else ")?;
+      write_if(f, hcb, cond, then, otherwise, depth)
+    }
+    _ => {
+      writeln!(f, "{indent}}} else {{")?;
+      write_block(f, hcb, otherwise, depth + 1)?;
+      writeln!(f, "{indent}}}")
+    }
+  }
+}
+
+impl fmt::Display for FvpDecompiledScript<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "// title: {}", self.hcb.title())?;
+    writeln!(
+      f,
+      "// globals: {} non-volatile, {} volatile",
+      self.hcb.non_volatile_global_count(),
+      self.hcb.volatile_global_count(),
+    )?;
+
+    for function in &self.functions {
+      writeln!(f)?;
+
+      if function.address == self.hcb.entry_point() {
+        writeln!(f, "// entry point")?;
+      }
+
+      writeln!(
+        f,
+        "function {}() {{ // args: {}, locals: {}",
+        function_name(function.address),
+        function.args,
+        function.locals,
+      )?;
+      write_block(f, self.hcb, &function.body, 1)?;
+      writeln!(f, "}}")?;
+    }
+
+    Ok(())
+  }
+}
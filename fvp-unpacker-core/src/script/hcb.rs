@@ -0,0 +1,387 @@
+use std::{borrow::Cow, fmt};
+
+use crate::{
+  error::{FvpError, FvpResult},
+  utils::{encoding::decode_string, sread::FvpBuffer},
+};
+
+#[derive(Clone, Debug)]
+pub struct FvpHcbSyscall {
+  pub name: String,
+  pub args: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FvpHcbOpcode {
+  Nop,
+  InitStack { args: u8, locals: u8 },
+  Call(u32),
+  Syscall(u16),
+  Ret,
+  RetV,
+  Jmp(u32),
+  Jz(u32),
+  PushNil,
+  PushTrue,
+  PushI32(i32),
+  PushI16(i16),
+  PushI8(i8),
+  PushF32(f32),
+  PushString(String),
+  PushGlobal(u16),
+  PushStack(i8),
+  PushGlobalTable(u16),
+  PushLocalTable(i8),
+  PushTop,
+  PushReturn,
+  PopGlobal(u16),
+  PopStack(i8),
+  PopGlobalTable(u16),
+  PopLocalTable(i8),
+  Neg,
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Mod,
+  BitTest,
+  And,
+  Or,
+  SetE,
+  SetNE,
+  SetG,
+  SetLE,
+  SetL,
+  SetGE,
+}
+
+impl fmt::Display for FvpHcbOpcode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Nop => write!(f, "nop"),
+      Self::InitStack { args, locals } => write!(f, "init_stack {args}, {locals}"),
+      Self::Call(addr) => write!(f, "call {addr:#010x}"),
+      Self::Syscall(id) => write!(f, "syscall {id}"),
+      Self::Ret => write!(f, "ret"),
+      Self::RetV => write!(f, "retv"),
+      Self::Jmp(addr) => write!(f, "jmp {addr:#010x}"),
+      Self::Jz(addr) => write!(f, "jz {addr:#010x}"),
+      Self::PushNil => write!(f, "push_nil"),
+      Self::PushTrue => write!(f, "push_true"),
+      Self::PushI32(x) => write!(f, "push_i32 {x}"),
+      Self::PushI16(x) => write!(f, "push_i16 {x}"),
+      Self::PushI8(x) => write!(f, "push_i8 {x}"),
+      Self::PushF32(x) => write!(f, "push_f32 {x:?}"),
+      Self::PushString(x) => write!(f, "push_string {x:?}"),
+      Self::PushGlobal(id) => write!(f, "push_global {id}"),
+      Self::PushStack(idx) => write!(f, "push_stack {idx}"),
+      Self::PushGlobalTable(id) => write!(f, "push_global_table {id}"),
+      Self::PushLocalTable(idx) => write!(f, "push_local_table {idx}"),
+      Self::PushTop => write!(f, "push_top"),
+      Self::PushReturn => write!(f, "push_return"),
+      Self::PopGlobal(id) => write!(f, "pop_global {id}"),
+      Self::PopStack(idx) => write!(f, "pop_stack {idx}"),
+      Self::PopGlobalTable(id) => write!(f, "pop_global_table {id}"),
+      Self::PopLocalTable(idx) => write!(f, "pop_local_table {idx}"),
+      Self::Neg => write!(f, "neg"),
+      Self::Add => write!(f, "add"),
+      Self::Sub => write!(f, "sub"),
+      Self::Mul => write!(f, "mul"),
+      Self::Div => write!(f, "div"),
+      Self::Mod => write!(f, "mod"),
+      Self::BitTest => write!(f, "bittest"),
+      Self::And => write!(f, "and"),
+      Self::Or => write!(f, "or"),
+      Self::SetE => write!(f, "sete"),
+      Self::SetNE => write!(f, "setne"),
+      Self::SetG => write!(f, "setg"),
+      Self::SetLE => write!(f, "setle"),
+      Self::SetL => write!(f, "setl"),
+      Self::SetGE => write!(f, "setge"),
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct FvpHcbInstruction {
+  pub address: u32,
+  pub opcode: FvpHcbOpcode,
+}
+
+impl fmt::Display for FvpHcbInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:#010x}: {}", self.address, self.opcode)
+  }
+}
+
+pub struct FvpHcbFunction<'a> {
+  pub address: u32,
+  pub args: u8,
+  pub locals: u8,
+  pub instructions: &'a [FvpHcbInstruction],
+}
+
+pub struct FvpHcb {
+  entry_point: u32,
+  non_volatile_global_count: u16,
+  volatile_global_count: u16,
+  game_mode: u16,
+  title: String,
+  syscalls: Vec<FvpHcbSyscall>,
+  instructions: Vec<FvpHcbInstruction>,
+}
+
+impl FvpHcb {
+  pub fn entry_point(&self) -> u32 {
+    self.entry_point
+  }
+
+  pub fn non_volatile_global_count(&self) -> u16 {
+    self.non_volatile_global_count
+  }
+
+  pub fn volatile_global_count(&self) -> u16 {
+    self.volatile_global_count
+  }
+
+  pub fn game_mode(&self) -> u16 {
+    self.game_mode
+  }
+
+  pub fn title(&self) -> &str {
+    &self.title
+  }
+
+  pub fn syscalls(&self) -> &[FvpHcbSyscall] {
+    &self.syscalls
+  }
+
+  pub fn syscall_name(&self, id: u16) -> Cow<'_, str> {
+    match self.syscalls.get(id as usize) {
+      Some(syscall) => Cow::Borrowed(&syscall.name),
+      None => Cow::Owned(format!("syscall_{id}")),
+    }
+  }
+
+  pub fn instructions(&self) -> &[FvpHcbInstruction] {
+    &self.instructions
+  }
+
+  /// Index of the instruction at `address`, if an instruction starts there.
+  pub fn instruction_index(&self, address: u32) -> Option<usize> {
+    self
+      .instructions
+      .binary_search_by_key(&address, |instruction| instruction.address)
+      .ok()
+  }
+
+  /// Split the bytecode into functions, each of which starts with `init_stack`.
+  pub fn functions(&self) -> Vec<FvpHcbFunction<'_>> {
+    let starts: Vec<_> = self
+      .instructions
+      .iter()
+      .enumerate()
+      .filter(|(_, instruction)| matches!(instruction.opcode, FvpHcbOpcode::InitStack { .. }))
+      .map(|(i, _)| i)
+      .collect();
+
+    starts
+      .iter()
+      .enumerate()
+      .map(|(i, &start)| {
+        let end = starts
+          .get(i + 1)
+          .copied()
+          .unwrap_or(self.instructions.len());
+
+        let FvpHcbOpcode::InitStack { args, locals } = self.instructions[start].opcode else {
+          unreachable!();
+        };
+
+        FvpHcbFunction {
+          address: self.instructions[start].address,
+          args,
+          locals,
+          instructions: &self.instructions[start..end],
+        }
+      })
+      .collect()
+  }
+
+  // TODO: zero-copy
+  pub fn parse(src: impl AsRef<[u8]>) -> FvpResult<Self> {
+    fn parse_inner(src: &[u8]) -> FvpResult<FvpHcb> {
+      let sys_desc_offset = src.sread::<u32>(0)? as usize;
+
+      if sys_desc_offset < 4 || sys_desc_offset > src.len() {
+        return Err(FvpError::OffsetTooLarge);
+      }
+
+      let instructions = parse_bytecode(&src[..sys_desc_offset])?;
+
+      let mut cursor = sys_desc_offset;
+
+      let entry_point: u32 = src.sread(cursor)?;
+      let non_volatile_global_count: u16 = src.sread(cursor + 4)?;
+      let volatile_global_count: u16 = src.sread(cursor + 6)?;
+      let game_mode: u16 = src.sread(cursor + 8)?;
+      let title_len = src.sread::<u8>(cursor + 10)? as usize;
+      let title: Cow<str> = src.sread(cursor + 11)?;
+      let title = title.into_owned();
+      cursor += 11 + title_len;
+
+      let syscall_count: u16 = src.sread(cursor)?;
+      cursor += 2;
+
+      let syscalls = (0..syscall_count)
+        .map(|_| {
+          let args: u8 = src.sread(cursor)?;
+          let name_len = src.sread::<u8>(cursor + 1)? as usize;
+          let name: Cow<str> = src.sread(cursor + 2)?;
+          cursor += 2 + name_len;
+
+          Ok(FvpHcbSyscall {
+            name: name.into_owned(),
+            args,
+          })
+        })
+        .collect::<FvpResult<_>>()?;
+
+      Ok(FvpHcb {
+        entry_point,
+        non_volatile_global_count,
+        volatile_global_count,
+        game_mode,
+        title,
+        syscalls,
+        instructions,
+      })
+    }
+
+    let src = src.as_ref();
+    parse_inner(src)
+  }
+}
+
+fn parse_bytecode(src: &[u8]) -> FvpResult<Vec<FvpHcbInstruction>> {
+  let mut instructions = Vec::new();
+  let mut offset = 4;
+
+  while offset < src.len() {
+    let address = offset as u32;
+    let opcode: u8 = src.sread(offset)?;
+    offset += 1;
+
+    let opcode = match opcode {
+      0x00 => FvpHcbOpcode::Nop,
+      0x01 => {
+        let args: u8 = src.sread(offset)?;
+        let locals: u8 = src.sread(offset + 1)?;
+        offset += 2;
+        FvpHcbOpcode::InitStack { args, locals }
+      }
+      0x02 => {
+        offset += 4;
+        FvpHcbOpcode::Call(src.sread(offset - 4)?)
+      }
+      0x03 => {
+        offset += 2;
+        FvpHcbOpcode::Syscall(src.sread(offset - 2)?)
+      }
+      0x04 => FvpHcbOpcode::Ret,
+      0x05 => FvpHcbOpcode::RetV,
+      0x06 => {
+        offset += 4;
+        FvpHcbOpcode::Jmp(src.sread(offset - 4)?)
+      }
+      0x07 => {
+        offset += 4;
+        FvpHcbOpcode::Jz(src.sread(offset - 4)?)
+      }
+      0x08 => FvpHcbOpcode::PushNil,
+      0x09 => FvpHcbOpcode::PushTrue,
+      0x0a => {
+        offset += 4;
+        FvpHcbOpcode::PushI32(src.sread(offset - 4)?)
+      }
+      0x0b => {
+        offset += 2;
+        FvpHcbOpcode::PushI16(src.sread(offset - 2)?)
+      }
+      0x0c => {
+        offset += 1;
+        FvpHcbOpcode::PushI8(src.sread(offset - 1)?)
+      }
+      0x0d => {
+        offset += 4;
+        FvpHcbOpcode::PushF32(src.sread(offset - 4)?)
+      }
+      0x0e => {
+        let len = src.sread::<u8>(offset)? as usize;
+        let bytes = src
+          .get((offset + 1)..(offset + 1 + len))
+          .ok_or(FvpError::OffsetTooLarge)?;
+        offset += 1 + len;
+
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        FvpHcbOpcode::PushString(decode_string(&bytes[..end])?.into_owned())
+      }
+      0x0f => {
+        offset += 2;
+        FvpHcbOpcode::PushGlobal(src.sread(offset - 2)?)
+      }
+      0x10 => {
+        offset += 1;
+        FvpHcbOpcode::PushStack(src.sread(offset - 1)?)
+      }
+      0x11 => {
+        offset += 2;
+        FvpHcbOpcode::PushGlobalTable(src.sread(offset - 2)?)
+      }
+      0x12 => {
+        offset += 1;
+        FvpHcbOpcode::PushLocalTable(src.sread(offset - 1)?)
+      }
+      0x13 => FvpHcbOpcode::PushTop,
+      0x14 => FvpHcbOpcode::PushReturn,
+      0x15 => {
+        offset += 2;
+        FvpHcbOpcode::PopGlobal(src.sread(offset - 2)?)
+      }
+      0x16 => {
+        offset += 1;
+        FvpHcbOpcode::PopStack(src.sread(offset - 1)?)
+      }
+      0x17 => {
+        offset += 2;
+        FvpHcbOpcode::PopGlobalTable(src.sread(offset - 2)?)
+      }
+      0x18 => {
+        offset += 1;
+        FvpHcbOpcode::PopLocalTable(src.sread(offset - 1)?)
+      }
+      0x19 => FvpHcbOpcode::Neg,
+      0x1a => FvpHcbOpcode::Add,
+      0x1b => FvpHcbOpcode::Sub,
+      0x1c => FvpHcbOpcode::Mul,
+      0x1d => FvpHcbOpcode::Div,
+      0x1e => FvpHcbOpcode::Mod,
+      0x1f => FvpHcbOpcode::BitTest,
+      0x20 => FvpHcbOpcode::And,
+      0x21 => FvpHcbOpcode::Or,
+      0x22 => FvpHcbOpcode::SetE,
+      0x23 => FvpHcbOpcode::SetNE,
+      0x24 => FvpHcbOpcode::SetG,
+      0x25 => FvpHcbOpcode::SetLE,
+      0x26 => FvpHcbOpcode::SetL,
+      0x27 => FvpHcbOpcode::SetGE,
+      opcode => {
+        return Err(FvpError::UnknownOpcode { address, opcode });
+      }
+    };
+
+    instructions.push(FvpHcbInstruction { address, opcode });
+  }
+
+  Ok(instructions)
+}
@@ -0,0 +1,10 @@
+pub mod assets;
+pub mod callgraph;
+pub mod decompile;
+pub mod hcb;
+pub mod voice;
+
+/// Strip the directories from a path referenced by scripts, e.g. `voice/yuk0001` -> `yuk0001`.
+pub fn entry_name(path: &str) -> &str {
+  path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
@@ -0,0 +1,97 @@
+use super::{
+  decompile::{FvpDecompiledScript, FvpExpr},
+  entry_name,
+};
+
+pub struct FvpVoiceSyscalls {
+  /// Syscalls which load or play a voice file, e.g. `AudioLoad(channel, "voice/yuk0001")`
+  pub voice: Vec<String>,
+  /// Syscalls which print a line of text, the last string argument is used as the text
+  pub text: Vec<String>,
+  /// Syscalls which set the name of the speaker for the next text line
+  pub speaker: Vec<String>,
+}
+
+impl Default for FvpVoiceSyscalls {
+  fn default() -> Self {
+    Self {
+      voice: vec!["AudioLoad".to_string()],
+      text: vec!["TextPrint".to_string()],
+      speaker: Vec::new(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FvpVoiceLine {
+  /// Address of the function where the text is printed
+  pub function: u32,
+  pub speaker: Option<String>,
+  pub text: String,
+  /// Voice file path as referenced by the script
+  pub voice: Option<String>,
+}
+
+impl FvpVoiceLine {
+  /// The entry name in `voice.bin`, i.e. the voice path without any directories.
+  pub fn voice_entry_name(&self) -> Option<&str> {
+    self.voice.as_deref().map(entry_name)
+  }
+}
+
+impl FvpDecompiledScript<'_> {
+  /// Pair every text line with the voice loaded and the speaker set before it in the same
+  /// function.
+  pub fn voice_lines(&self, syscalls: &FvpVoiceSyscalls) -> Vec<FvpVoiceLine> {
+    let hcb = self.hcb();
+    let mut lines = Vec::new();
+
+    for function in self.functions() {
+      let mut voice = None;
+      let mut speaker = None;
+
+      let mut visit = |expr: &FvpExpr| {
+        let FvpExpr::Syscall(id, args) = expr else {
+          return;
+        };
+
+        let name = hcb.syscall_name(*id);
+        let strings: Vec<_> = args
+          .iter()
+          .filter_map(|arg| match arg {
+            FvpExpr::Str(x) => Some(x),
+            _ => None,
+          })
+          .collect();
+
+        let Some(last) = strings.last() else {
+          return;
+        };
+
+        if syscalls.voice.iter().any(|x| *x == name) {
+          voice = Some(last.to_string());
+        } else if syscalls.speaker.iter().any(|x| *x == name) {
+          speaker = Some(last.to_string());
+        } else if syscalls.text.iter().any(|x| *x == name) {
+          let line_speaker = match strings.as_slice() {
+            [first, _, ..] => Some(first.to_string()),
+            _ => speaker.take(),
+          };
+
+          lines.push(FvpVoiceLine {
+            function: function.address,
+            speaker: line_speaker,
+            text: last.to_string(),
+            voice: voice.take(),
+          });
+        }
+      };
+
+      for stmt in &function.body {
+        stmt.walk_exprs(&mut visit);
+      }
+    }
+
+    lines
+  }
+}
@@ -11,7 +11,7 @@ pub trait FvpBuffer {
 
 impl FvpBuffer for [u8] {
   fn sread<'a, N: FvpRead<'a>>(&'a self, offset: usize) -> FvpResult<N> {
-    N::from_buffer(&self[offset..])
+    N::from_buffer(self.get(offset..).ok_or(FvpError::OffsetTooLarge)?)
   }
 }
 
@@ -21,20 +21,74 @@ pub trait FvpRead<'a> {
     Self: Sized;
 }
 
+impl FvpRead<'_> for u8 {
+  fn from_buffer(buffer: &[u8]) -> FvpResult<Self> {
+    match buffer.first() {
+      Some(data) => Ok(*data),
+      None => Err(FvpError::OffsetTooLarge),
+    }
+  }
+}
+
+impl FvpRead<'_> for i8 {
+  fn from_buffer(buffer: &[u8]) -> FvpResult<Self> {
+    match buffer.first() {
+      Some(data) => Ok(*data as i8),
+      None => Err(FvpError::OffsetTooLarge),
+    }
+  }
+}
+
 impl FvpRead<'_> for u16 {
   fn from_buffer(buffer: &[u8]) -> FvpResult<Self> {
-    match buffer[..2].try_into() {
-      Ok(data) => Ok(u16::from_le_bytes(data)),
-      Err(_) => Err(FvpError::OffsetTooLarge),
+    match buffer.first_chunk() {
+      Some(data) => Ok(u16::from_le_bytes(*data)),
+      None => Err(FvpError::OffsetTooLarge),
     }
   }
 }
 
 impl FvpRead<'_> for u32 {
   fn from_buffer(buffer: &[u8]) -> FvpResult<Self> {
-    match buffer[..4].try_into() {
-      Ok(data) => Ok(u32::from_le_bytes(data)),
-      Err(_) => Err(FvpError::OffsetTooLarge),
+    match buffer.first_chunk() {
+      Some(data) => Ok(u32::from_le_bytes(*data)),
+      None => Err(FvpError::OffsetTooLarge),
+    }
+  }
+}
+
+impl FvpRead<'_> for u64 {
+  fn from_buffer(buffer: &[u8]) -> FvpResult<Self> {
+    match buffer.first_chunk() {
+      Some(data) => Ok(u64::from_le_bytes(*data)),
+      None => Err(FvpError::OffsetTooLarge),
+    }
+  }
+}
+
+impl FvpRead<'_> for i16 {
+  fn from_buffer(buffer: &[u8]) -> FvpResult<Self> {
+    match buffer.first_chunk() {
+      Some(data) => Ok(i16::from_le_bytes(*data)),
+      None => Err(FvpError::OffsetTooLarge),
+    }
+  }
+}
+
+impl FvpRead<'_> for i32 {
+  fn from_buffer(buffer: &[u8]) -> FvpResult<Self> {
+    match buffer.first_chunk() {
+      Some(data) => Ok(i32::from_le_bytes(*data)),
+      None => Err(FvpError::OffsetTooLarge),
+    }
+  }
+}
+
+impl FvpRead<'_> for f32 {
+  fn from_buffer(buffer: &[u8]) -> FvpResult<Self> {
+    match buffer.first_chunk() {
+      Some(data) => Ok(f32::from_le_bytes(*data)),
+      None => Err(FvpError::OffsetTooLarge),
     }
   }
 }
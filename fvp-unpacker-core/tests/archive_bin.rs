@@ -1,4 +1,4 @@
-use fvp_unpacker_core::prelude::*;
+use fvp_unpacker_core::{archive::group::name_prefix, prelude::*};
 
 const SINGLE_ENTRY_BIN: &[u8] = include_bytes!("single-entry.bin");
 const MULTIPLE_ENTRIES_BIN: &[u8] = include_bytes!("multiple-entries.bin");
@@ -35,6 +35,21 @@ fn parse_multiple_entries_bin_archive() {
   assert_eq!(entry3.data(), b"and everything");
 }
 
+#[test]
+fn parse_entry_offsets() {
+  let arc = FvpBin::parse(MULTIPLE_ENTRIES_BIN).unwrap();
+  let entries = arc.entries();
+
+  // the data of the entries is stored in order at the end of the archive
+  let data_base = MULTIPLE_ENTRIES_BIN.len() - 18 - 12 - 14;
+  assert_eq!(entries[0].offset(), Some(data_base));
+  assert_eq!(entries[1].offset(), Some(data_base + 18));
+  assert_eq!(entries[2].offset(), Some(data_base + 18 + 12));
+
+  // entries which are not parsed from an archive have no offset
+  assert_eq!(FvpBinEntry::new("file1", *b"data").offset(), None);
+}
+
 #[test]
 fn write_single_entry_bin_archive() {
   let mut arc = FvpBin::default();
@@ -61,3 +76,20 @@ fn write_multiple_entries_bin_archive() {
 
   assert_eq!(bytes, MULTIPLE_ENTRIES_BIN);
 }
+
+#[test]
+fn group_entries_by_prefix() {
+  let arc = FvpBin::new([
+    FvpBinEntry::new("yuk0001", *b"1"),
+    FvpBinEntry::new("nan_0001", *b"2"),
+    FvpBinEntry::new("yuk0002", *b"3"),
+    FvpBinEntry::new("0001", *b"4"),
+  ]);
+
+  let groups = arc.group_by(name_prefix);
+
+  assert_eq!(groups.keys().copied().collect::<Vec<_>>(), ["nan", "yuk"]);
+
+  let yuk: Vec<_> = groups["yuk"].iter().map(|entry| entry.filename()).collect();
+  assert_eq!(yuk, ["yuk0001", "yuk0002"]);
+}
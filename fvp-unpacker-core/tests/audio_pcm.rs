@@ -0,0 +1,50 @@
+use std::{io::Cursor, time::Duration};
+
+use fvp_unpacker_core::audio::pcm::FvpPcm;
+
+fn pcm(samples: Vec<i16>) -> FvpPcm {
+  FvpPcm {
+    sample_rate: 4,
+    channels: 2,
+    samples,
+  }
+}
+
+#[test]
+fn render_loop_with_fade() {
+  // intro of 1 frame, and a loop of 2 frames
+  let audio = pcm(vec![1, -1, 2, -2, 3, -3, 4, -4]);
+
+  let looped = audio.looped(1, 2, 2, Duration::from_secs(1));
+
+  assert_eq!(
+    looped.samples,
+    [
+      1, -1, 2, -2, 3, -3, // intro and the loop
+      2, -2, 3, -3, // the loop again
+      2, -2, 2, -2, 1, -1, 1, -1, // fade out of 4 frames from the start of the loop
+    ]
+  );
+}
+
+#[test]
+fn write_flac_lossless() {
+  let samples: Vec<i16> = (0..10000)
+    .flat_map(|i| {
+      let x = ((i as f64 / 20.0).sin() * 10000.0) as i16;
+      [x, x / 2 + (i % 7) as i16]
+    })
+    .collect();
+  let audio = pcm(samples);
+
+  let mut flac = Vec::new();
+  audio.write_flac(&mut flac).unwrap();
+  assert!(flac.len() < audio.samples.len() * 2);
+
+  let mut reader = claxon::FlacReader::new(Cursor::new(flac)).unwrap();
+  assert_eq!(reader.streaminfo().channels, 2);
+  assert_eq!(reader.streaminfo().samples, Some(10000));
+
+  let decoded: Vec<i16> = reader.samples().map(|x| x.unwrap() as i16).collect();
+  assert_eq!(decoded, audio.samples);
+}
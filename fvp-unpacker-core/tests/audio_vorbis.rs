@@ -0,0 +1,188 @@
+use fvp_unpacker_core::audio::{
+  format::{FvpAudioCodec, FvpAudioFormat},
+  ogg::{FvpOggPage, FvpOggPages},
+  verify::{FvpOggIssue, FvpOggProblem, verify_ogg},
+  vorbis::FvpVorbisInfo,
+};
+
+fn ogg_page(
+  header_type: u8,
+  granule_position: u64,
+  sequence_number: u32,
+  packet: &[u8],
+) -> Vec<u8> {
+  let mut page = Vec::new();
+  page.extend_from_slice(b"OggS");
+  page.push(0);
+  page.push(header_type);
+  page.extend_from_slice(&granule_position.to_le_bytes());
+  page.extend_from_slice(&1u32.to_le_bytes());
+  page.extend_from_slice(&sequence_number.to_le_bytes());
+  page.extend_from_slice(&0u32.to_le_bytes());
+
+  let mut segments = vec![255; packet.len() / 255];
+  segments.push((packet.len() % 255) as u8);
+  page.push(segments.len() as u8);
+  page.extend_from_slice(&segments);
+  page.extend_from_slice(packet);
+
+  let checksum = FvpOggPage::parse(&page).unwrap().compute_checksum();
+  page[22..26].copy_from_slice(&checksum.to_le_bytes());
+
+  page
+}
+
+/// A Vorbis stream with only the identification and comment headers, and an empty audio page.
+fn vorbis_stream(comments: &[&str], samples: u64) -> Vec<u8> {
+  let mut identification = b"\x01vorbis".to_vec();
+  identification.extend_from_slice(&0u32.to_le_bytes());
+  identification.push(2);
+  identification.extend_from_slice(&44100u32.to_le_bytes());
+  identification.extend_from_slice(&[0; 12]);
+  identification.extend_from_slice(&[0xb8, 0x01]);
+
+  let mut comment = b"\x03vorbis".to_vec();
+  let string = |comment: &mut Vec<u8>, string: &str| {
+    comment.extend_from_slice(&(string.len() as u32).to_le_bytes());
+    comment.extend_from_slice(string.as_bytes());
+  };
+  string(&mut comment, "test");
+  comment.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+  for x in comments {
+    string(&mut comment, x);
+  }
+  comment.push(1);
+
+  [
+    ogg_page(0x02, 0, 0, &identification),
+    // long enough to span multiple segments
+    ogg_page(0x00, u64::MAX, 1, &comment),
+    ogg_page(0x04, samples, 2, &[]),
+  ]
+  .concat()
+}
+
+#[test]
+fn parse_vorbis_info() {
+  let padding = "X".repeat(300);
+  let stream = vorbis_stream(
+    &["TITLE=bgm01", "LOOPSTART=44100", "loopend=88200", &padding],
+    441000,
+  );
+
+  let pages: Vec<_> = FvpOggPages::new(&stream).collect::<Result<_, _>>().unwrap();
+  assert_eq!(pages.len(), 3);
+  assert!(pages[0].is_first());
+  assert!(pages[2].is_last());
+
+  let info = FvpVorbisInfo::parse(&stream).unwrap();
+  assert_eq!(info.channels, 2);
+  assert_eq!(info.sample_rate, 44100);
+  assert_eq!(info.vendor, "test");
+  assert_eq!(info.comment("title"), Some("bgm01"));
+  assert_eq!(info.samples, 441000);
+  assert_eq!(info.duration().as_secs(), 10);
+  assert_eq!(info.loop_start(), Some(44100));
+  assert_eq!(info.loop_length(), Some(44100));
+}
+
+#[test]
+fn verify_ogg_pages() {
+  let stream = vorbis_stream(&["TITLE=bgm01"], 441000);
+  assert!(verify_ogg(&stream, false).is_empty());
+
+  let problems = |stream: &[u8]| -> Vec<_> {
+    verify_ogg(stream, false)
+      .into_iter()
+      .map(|issue| (issue.page.map(|(index, _)| index), issue.problem))
+      .collect()
+  };
+
+  let sizes: Vec<_> = FvpOggPages::new(&stream)
+    .map(|page| page.unwrap().size())
+    .collect();
+  let (first, second) = (sizes[0], sizes[1]);
+
+  // corrupt the granule position of the last page
+  let mut corrupted = stream.clone();
+  corrupted[first + second + 6] ^= 0xff;
+  let last = FvpOggPages::new(&stream).last().unwrap().unwrap();
+  assert!(matches!(
+    problems(&corrupted)[..],
+    [(Some(2), FvpOggProblem::ChecksumMismatch { expected, .. })] if expected == last.checksum
+  ));
+
+  // drop the comment page
+  let dropped = [&stream[..first], &stream[(first + second)..]].concat();
+  assert_eq!(
+    problems(&dropped),
+    [(
+      Some(1),
+      FvpOggProblem::SequenceMismatch {
+        expected: 1,
+        found: 2
+      }
+    )]
+  );
+
+  // truncate before the end-of-stream page
+  assert_eq!(
+    problems(&stream[..(first + second)]),
+    [(None, FvpOggProblem::MissingEndOfStream)]
+  );
+}
+
+#[test]
+fn verify_opus_without_decoding() {
+  let mut head = b"OpusHead\x01\x02".to_vec();
+  head.extend_from_slice(&312u16.to_le_bytes());
+  head.extend_from_slice(&[0; 7]);
+  let mut tags = b"OpusTags".to_vec();
+  tags.extend_from_slice(&4u32.to_le_bytes());
+  tags.extend_from_slice(b"test");
+  tags.extend_from_slice(&0u32.to_le_bytes());
+
+  let stream = [
+    ogg_page(0x02, 0, 0, &head),
+    ogg_page(0x00, 0, 1, &tags),
+    ogg_page(0x04, 48000, 2, &[]),
+  ]
+  .concat();
+  assert!(verify_ogg(&stream, true).is_empty());
+
+  // the Vorbis stream has no setup header, so only decoding it fails
+  let vorbis = verify_ogg(vorbis_stream(&[], 0), true);
+  assert!(matches!(
+    vorbis[..],
+    [FvpOggIssue {
+      page: None,
+      problem: FvpOggProblem::Decoding(_)
+    }]
+  ));
+}
+
+#[test]
+fn audio_format_mismatches() {
+  let vorbis = FvpAudioFormat::parse(vorbis_stream(&[], 0)).unwrap();
+  assert_eq!(
+    vorbis,
+    FvpAudioFormat {
+      codec: FvpAudioCodec::Vorbis,
+      channels: 2,
+      sample_rate: 44100,
+    }
+  );
+  assert!(vorbis.mismatches(&vorbis).is_empty());
+
+  // mono, pre-skip 312, input sample rate unspecified
+  let mut head = b"OpusHead\x01\x01".to_vec();
+  head.extend_from_slice(&312u16.to_le_bytes());
+  head.extend_from_slice(&[0; 7]);
+  let opus = FvpAudioFormat::parse(ogg_page(0x02, 0, 0, &head)).unwrap();
+  assert_eq!(opus.codec, FvpAudioCodec::Opus);
+  assert_eq!(opus.channels, 1);
+  assert_eq!(opus.sample_rate, 48000);
+
+  assert_eq!(vorbis.mismatches(&opus).len(), 3);
+  assert!(FvpAudioFormat::parse(ogg_page(0x02, 0, 0, b"unknown")).is_err());
+}
@@ -0,0 +1,18 @@
+/// Only the header of a 4x4 BGRA hzc file, which is enough to group the images.
+pub fn hzc_header(count: u32) -> Vec<u8> {
+  let mut bytes = Vec::new();
+  bytes.extend_from_slice(b"hzc1");
+  bytes.extend_from_slice(&0u32.to_le_bytes());
+  bytes.extend_from_slice(&32u32.to_le_bytes());
+
+  bytes.extend_from_slice(b"NVSG");
+  // unknown, color, width, height, offset x and offset y
+  for x in [0x100u16, 2, 4, 4, 0, 0] {
+    bytes.extend_from_slice(&x.to_le_bytes());
+  }
+  bytes.extend_from_slice(&0u32.to_le_bytes());
+  bytes.extend_from_slice(&count.to_le_bytes());
+  bytes.extend_from_slice(&0u64.to_le_bytes());
+
+  bytes
+}
@@ -0,0 +1,39 @@
+use fvp_unpacker_core::{archive::hzc::FvpHzcEntry, image::animation::FvpAnimation};
+use imgref::ImgVec;
+use rgb::Bgra;
+
+fn frame(width: usize, height: usize, offset: (u16, u16)) -> FvpHzcEntry<Bgra<u8>> {
+  let pixel = Bgra {
+    b: 0,
+    g: 0,
+    r: 255,
+    a: 255,
+  };
+
+  FvpHzcEntry {
+    offset,
+    data: ImgVec::new(vec![pixel; width * height], width, height),
+  }
+}
+
+#[test]
+fn write_animation() {
+  let animation = FvpAnimation::new([frame(2, 2, (10, 20)), frame(1, 1, (12, 21))]).with_delay(200);
+
+  // placed on the smallest canvas relative to the top-left frame
+  for frame in animation.frames() {
+    assert_eq!((frame.data.width(), frame.data.height()), (3, 2));
+  }
+  assert_eq!(animation.frames()[1].data[(2usize, 1usize)].a, 255);
+  assert_eq!(animation.frames()[1].data[(0usize, 0usize)].a, 0);
+
+  let mut apng = Vec::new();
+  animation.write_apng(&mut apng).unwrap();
+  let count = |chunk: &[u8]| apng.windows(4).filter(|x| *x == chunk).count();
+  assert_eq!(count(b"acTL"), 1);
+  assert_eq!(count(b"fcTL"), 2);
+
+  let mut gif = Vec::new();
+  animation.write_gif(&mut gif).unwrap();
+  assert_eq!(&gif[..6], b"GIF89a");
+}
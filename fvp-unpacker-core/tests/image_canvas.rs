@@ -0,0 +1,167 @@
+use fvp_unpacker_core::{
+  archive::hzc::FvpHzcEntry,
+  image::{atlas::FvpAtlas, trim::FvpTrim},
+  prelude::*,
+};
+use imgref::ImgVec;
+use rgb::Bgra;
+
+#[test]
+fn place_image_on_canvas() {
+  let pixel = Bgra {
+    b: 1,
+    g: 2,
+    r: 3,
+    a: 255,
+  };
+  let image = FvpHzcEntry {
+    offset: (2, 1),
+    data: ImgVec::new(vec![pixel; 6], 3, 2),
+  };
+
+  assert_eq!(image.canvas_size(), (5, 3));
+
+  let placed = image.place_on_canvas(4, 4);
+  assert_eq!(placed.offset, (0, 0));
+  assert_eq!((placed.data.width(), placed.data.height()), (4, 4));
+
+  for (y, row) in placed.data.rows().enumerate() {
+    for (x, actual) in row.iter().enumerate() {
+      let expected = if x >= 2 && (1..3).contains(&y) {
+        pixel
+      } else {
+        Bgra::default()
+      };
+      assert_eq!(*actual, expected, "pixel at ({x}, {y})");
+    }
+  }
+}
+
+#[test]
+fn place_image_off_canvas() {
+  let pixel = Bgra {
+    b: 1,
+    g: 2,
+    r: 3,
+    a: 255,
+  };
+
+  for offset in [(5, 200), (200, 5), (4, 4)] {
+    let image = FvpHzcEntry {
+      offset,
+      data: ImgVec::new(vec![pixel; 6], 3, 2),
+    };
+
+    let placed = image.place_on_canvas(4, 4);
+    assert_eq!((placed.data.width(), placed.data.height()), (4, 4));
+    assert!(placed.data.pixels().all(|x| x == Bgra::default()));
+  }
+}
+
+#[test]
+fn parse_truncated_header() {
+  let mut src = Vec::new();
+  src.extend_from_slice(b"hzc1");
+  src.extend_from_slice(&0u32.to_le_bytes());
+  // the header is 32 bytes, but only 8 are present
+  src.extend_from_slice(&32u32.to_le_bytes());
+  src.extend_from_slice(b"NVSG\0\0\0\0");
+
+  assert!(matches!(
+    DynamicFvpHzc::parse_header(&src),
+    Err(FvpError::OffsetTooLarge)
+  ));
+}
+
+#[test]
+fn trim_transparent_borders() {
+  let mut buf = vec![Bgra::default(); 5 * 4];
+  let opaque = Bgra {
+    b: 0,
+    g: 0,
+    r: 0,
+    a: 255,
+  };
+  buf[5 + 1] = opaque;
+  buf[2 * 5 + 3] = opaque;
+
+  let image = FvpHzcEntry {
+    offset: (10, 20),
+    data: ImgVec::new(buf, 5, 4),
+  };
+
+  let (trimmed, trim) = image.trim();
+
+  assert_eq!(
+    trim,
+    FvpTrim {
+      x: 1,
+      y: 1,
+      width: 3,
+      height: 2,
+      source_width: 5,
+      source_height: 4,
+    }
+  );
+  assert_eq!(trimmed.offset, (11, 21));
+  assert_eq!((trimmed.data.width(), trimmed.data.height()), (3, 2));
+  assert_eq!(trimmed.data[(0usize, 0usize)], opaque);
+  assert_eq!(trimmed.data[(2usize, 1usize)], opaque);
+}
+
+#[test]
+fn trim_empty_image() {
+  let image = FvpHzcEntry {
+    offset: (10, 20),
+    // `ImgVec::new` requires a non-zero width as the stride
+    data: ImgVec::new_stride(Vec::<Bgra<u8>>::new(), 0, 0, 1),
+  };
+
+  let (trimmed, trim) = image.trim();
+
+  assert_eq!((trim.width, trim.height), (0, 0));
+  assert_eq!(trimmed.offset, (10, 20));
+  assert_eq!((trimmed.data.width(), trimmed.data.height()), (0, 0));
+
+  let transparent = FvpHzcEntry {
+    offset: (0, 0),
+    data: ImgVec::new(vec![Bgra::default(); 6], 3, 2),
+  };
+
+  let (trimmed, _) = transparent.trim();
+  assert_eq!((trimmed.data.width(), trimmed.data.height()), (1, 1));
+}
+
+#[test]
+fn pack_atlas() {
+  let frame = |width, height, offset| FvpHzcEntry {
+    offset,
+    data: ImgVec::new(vec![Bgra::<u8>::default(); width * height], width, height),
+  };
+  let (small, large, wide) = (
+    frame(2, 2, (5, 6)),
+    frame(4, 4, (0, 0)),
+    frame(6, 1, (1, 1)),
+  );
+
+  let atlas = FvpAtlas::pack(
+    [
+      ("small".to_string(), &small),
+      ("large".to_string(), &large),
+      ("wide".to_string(), &wide),
+    ],
+    0,
+  );
+
+  let frames = atlas.frames();
+  // sorted from the tallest to the shortest
+  assert_eq!(frames[0].name, "large");
+  assert_eq!((frames[0].x, frames[0].y), (0, 0));
+  assert_eq!((frames[1].x, frames[1].y), (4, 0));
+  assert_eq!(frames[1].offset, (5, 6));
+  // the third frame does not fit in the first row
+  assert_eq!((frames[2].x, frames[2].y), (0, 4));
+
+  let image = atlas.image();
+  assert_eq!((image.data.width(), image.data.height()), (6, 5));
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::hzc_header;
+use fvp_unpacker_core::{image::cg::FvpEventCg, prelude::*};
+
+#[test]
+fn group_event_cgs() {
+  let arc = FvpBin::new([
+    FvpBinEntry::new("EV_001", hzc_header(0)),
+    FvpBinEntry::new("EV_001_a", hzc_header(0)),
+    FvpBinEntry::new("EV_001_a_1", hzc_header(2)),
+    FvpBinEntry::new("EV_002_b", hzc_header(0)),
+    FvpBinEntry::new("CHR_a", hzc_header(0)),
+  ]);
+
+  let layers = |layers: &[(&str, usize)]| {
+    layers
+      .iter()
+      .map(|(name, frame)| (name.to_string(), *frame))
+      .collect::<Vec<_>>()
+  };
+
+  assert_eq!(
+    arc.event_cgs("EV_"),
+    [
+      FvpEventCg {
+        name: "EV_001".to_string(),
+        layers: layers(&[("EV_001", 0)]),
+      },
+      FvpEventCg {
+        name: "EV_001_a".to_string(),
+        layers: layers(&[("EV_001", 0), ("EV_001_a", 0)]),
+      },
+      FvpEventCg {
+        name: "EV_001_a_1-0".to_string(),
+        layers: layers(&[("EV_001", 0), ("EV_001_a", 0), ("EV_001_a_1", 0)]),
+      },
+      FvpEventCg {
+        name: "EV_001_a_1-1".to_string(),
+        layers: layers(&[("EV_001", 0), ("EV_001_a", 0), ("EV_001_a_1", 1)]),
+      },
+      FvpEventCg {
+        name: "EV_002_b".to_string(),
+        layers: layers(&[("EV_002_b", 0)]),
+      },
+    ]
+  );
+}
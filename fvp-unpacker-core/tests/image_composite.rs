@@ -0,0 +1,161 @@
+use fvp_unpacker_core::{archive::hzc::FvpHzcEntry, prelude::*};
+use imgref::ImgVec;
+use rgb::Bgra;
+
+fn solid(width: usize, height: usize, pixel: Bgra<u8>) -> FvpHzcEntry<Bgra<u8>> {
+  FvpHzcEntry {
+    offset: (0, 0),
+    data: ImgVec::new(vec![pixel; width * height], width, height),
+  }
+}
+
+const RED: Bgra<u8> = Bgra {
+  b: 0,
+  g: 0,
+  r: 255,
+  a: 255,
+};
+
+#[test]
+fn composite_opaque_with_clipping() {
+  let mut base = solid(4, 4, RED);
+  let blue = Bgra {
+    b: 255,
+    g: 0,
+    r: 0,
+    a: 255,
+  };
+
+  base.composite_over(&solid(2, 2, blue), 3, -1, FvpAlpha::Straight);
+
+  for (y, row) in base.data.rows().enumerate() {
+    for (x, pixel) in row.iter().enumerate() {
+      let expected = if x == 3 && y == 0 { blue } else { RED };
+      assert_eq!(*pixel, expected, "pixel at ({x}, {y})");
+    }
+  }
+}
+
+#[test]
+fn composite_straight_alpha() {
+  let mut base = solid(1, 1, RED);
+  let half_blue = Bgra {
+    b: 255,
+    g: 0,
+    r: 0,
+    a: 128,
+  };
+
+  base.composite_over(&solid(1, 1, half_blue), 0, 0, FvpAlpha::Straight);
+  assert_eq!(
+    base.data.buf()[0],
+    Bgra {
+      b: 128,
+      g: 0,
+      r: 127,
+      a: 255
+    }
+  );
+
+  let mut transparent = solid(1, 1, Bgra::default());
+  transparent.composite_over(&solid(1, 1, half_blue), 0, 0, FvpAlpha::Straight);
+  assert_eq!(transparent.data.buf()[0], half_blue);
+}
+
+#[test]
+fn composite_premultiplied_alpha() {
+  let mut base = solid(1, 1, RED);
+  let half_blue = Bgra {
+    b: 128,
+    g: 0,
+    r: 0,
+    a: 128,
+  };
+
+  base.composite_over(&solid(1, 1, half_blue), 0, 0, FvpAlpha::Premultiplied);
+  assert_eq!(
+    base.data.buf()[0],
+    Bgra {
+      b: 128,
+      g: 0,
+      r: 127,
+      a: 255
+    }
+  );
+}
+
+#[test]
+fn compose_layers_in_order() {
+  let green = Bgra {
+    b: 0,
+    g: 255,
+    r: 0,
+    a: 255,
+  };
+  let blue = Bgra {
+    b: 255,
+    g: 0,
+    r: 0,
+    a: 255,
+  };
+
+  let mut first = solid(2, 2, green);
+  first.offset = (1, 1);
+  let mut second = solid(1, 1, blue);
+  second.offset = (2, 2);
+
+  let mut compositor = FvpCompositor::new(solid(4, 4, RED));
+  compositor.add_layer(&first).add_layer(&second);
+  let image = compositor.into_image();
+
+  assert_eq!(image.data[(0usize, 0usize)], RED);
+  assert_eq!(image.data[(1usize, 1usize)], green);
+  assert_eq!(image.data[(2usize, 2usize)], blue);
+  assert_eq!(image.data[(3usize, 3usize)], RED);
+}
+
+#[test]
+fn compose_layers_relative_to_base() {
+  let blue = Bgra {
+    b: 255,
+    g: 0,
+    r: 0,
+    a: 255,
+  };
+
+  let mut base = solid(4, 4, RED);
+  base.offset = (10, 20);
+  let mut layer = solid(1, 1, blue);
+  layer.offset = (2, 1);
+
+  let mut compositor = FvpCompositor::new(base);
+  compositor.add_layer(&layer);
+  let image = compositor.into_image();
+
+  assert_eq!(image.offset, (10, 20));
+  assert_eq!(image.data[(2usize, 1usize)], blue);
+  assert_eq!(image.data.buf().iter().filter(|x| **x == blue).count(), 1);
+}
+
+#[test]
+fn compose_screen_layers() {
+  let blue = Bgra {
+    b: 255,
+    g: 0,
+    r: 0,
+    a: 255,
+  };
+
+  let mut base = solid(4, 4, RED);
+  base.offset = (10, 20);
+  let mut layer = solid(1, 1, blue);
+  layer.offset = (12, 21);
+
+  let mut compositor = FvpCompositor::new(base);
+  compositor.add_screen_layer(&layer);
+  let image = compositor.into_image();
+
+  assert_eq!(image.offset, (10, 20));
+  assert_eq!(image.data[(2usize, 1usize)], blue);
+  assert_eq!(image.data.buf().iter().filter(|x| **x == blue).count(), 1);
+}
@@ -0,0 +1,85 @@
+use std::io::{Cursor, Read};
+
+use fvp_unpacker_core::{
+  archive::hzc::FvpHzcEntry,
+  image::layered::{FvpLayer, FvpLayeredImage},
+};
+use imgref::ImgVec;
+use rgb::Bgra;
+use zip::ZipArchive;
+
+fn layer(name: &str, width: usize, height: usize, x: i32, y: i32, visible: bool) -> FvpLayer {
+  let pixel = Bgra {
+    b: 0,
+    g: 0,
+    r: 255,
+    a: 255,
+  };
+
+  FvpLayer {
+    name: name.to_string(),
+    image: FvpHzcEntry {
+      offset: (0, 0),
+      data: ImgVec::new(vec![pixel; width * height], width, height),
+    },
+    x,
+    y,
+    visible,
+  }
+}
+
+fn document() -> FvpLayeredImage {
+  let mut document = FvpLayeredImage::new(4, 4);
+  document
+    .add_layer(layer("base", 4, 4, 0, 0, true))
+    .add_layer(layer("表情-0", 2, 2, 1, 1, true))
+    .add_layer(layer("表情-1", 2, 2, 1, 1, false));
+  document
+}
+
+#[test]
+fn write_ora_document() {
+  let mut ora = Cursor::new(Vec::new());
+  document().write_ora(&mut ora).unwrap();
+
+  let mut zip = ZipArchive::new(ora).unwrap();
+  assert_eq!(zip.by_index(0).unwrap().name(), "mimetype");
+
+  let mut stack = String::new();
+  zip
+    .by_name("stack.xml")
+    .unwrap()
+    .read_to_string(&mut stack)
+    .unwrap();
+
+  // the top layer comes first
+  let top = stack.find("name=\"表情-1\"").unwrap();
+  let bottom = stack.find("name=\"base\"").unwrap();
+  assert!(top < bottom);
+  assert!(stack.contains("src=\"data/layer2.png\" x=\"1\" y=\"1\" visibility=\"hidden\""));
+
+  for name in [
+    "data/layer0.png",
+    "mergedimage.png",
+    "Thumbnails/thumbnail.png",
+  ] {
+    assert!(zip.by_name(name).is_ok());
+  }
+}
+
+#[test]
+fn write_psd_document() {
+  let mut psd = Vec::new();
+  document().write_psd(&mut psd).unwrap();
+
+  assert_eq!(&psd[..4], b"8BPS");
+  // channels, height and width
+  assert_eq!(&psd[12..14], &4u16.to_be_bytes());
+  assert_eq!(&psd[14..18], &4u32.to_be_bytes());
+  assert_eq!(&psd[18..22], &4u32.to_be_bytes());
+
+  // the merged image is at the end, as raw planar data
+  let merged = &psd[psd.len() - 4 * 16..];
+  assert!(merged[..16].iter().all(|&r| r == 255));
+  assert!(merged[16..48].iter().all(|&gb| gb == 0));
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::hzc_header;
+use fvp_unpacker_core::prelude::*;
+
+#[test]
+fn list_tachies() {
+  let arc = FvpBin::new([
+    FvpBinEntry::new("CHR_雪々_喜_着物U", hzc_header(0)),
+    FvpBinEntry::new("CHR_雪々_喜_着物U_表情", hzc_header(12)),
+    FvpBinEntry::new("CHR_雪々_頬", hzc_header(2)),
+    FvpBinEntry::new("EV_001", hzc_header(0)),
+  ]);
+
+  let tachies = arc.tachies("CHR_");
+  assert_eq!(tachies.len(), 2);
+
+  assert_eq!(tachies[0].name, "CHR_雪々_喜_着物U");
+  assert_eq!(tachies[0].character, "雪々");
+  assert_eq!(tachies[0].pose.as_deref(), Some("喜"));
+  assert_eq!(tachies[0].outfit.as_deref(), Some("着物U"));
+  assert_eq!((tachies[0].width, tachies[0].height), (4, 4));
+  assert_eq!(tachies[0].facial_expressions, Some(12));
+
+  assert_eq!(tachies[1].pose.as_deref(), Some("頬"));
+  assert_eq!(tachies[1].outfit, None);
+  assert_eq!(tachies[1].facial_expressions, None);
+}
@@ -0,0 +1,257 @@
+use std::collections::BTreeSet;
+
+use fvp_unpacker_core::{
+  prelude::*,
+  script::{
+    assets::{DEFAULT_IMAGE_SYSCALLS, unreferenced_entries},
+    hcb::FvpHcbOpcode,
+    voice::FvpVoiceSyscalls,
+  },
+};
+
+fn build_hcb(code: &[u8], syscalls: &[(&str, u8)]) -> Vec<u8> {
+  let mut bytes = Vec::new();
+  bytes.extend_from_slice(&(code.len() as u32 + 4).to_le_bytes());
+  bytes.extend_from_slice(code);
+
+  // entry point, non-volatile globals, volatile globals and game mode
+  bytes.extend_from_slice(&4u32.to_le_bytes());
+  bytes.extend_from_slice(&3u16.to_le_bytes());
+  bytes.extend_from_slice(&0u16.to_le_bytes());
+  bytes.extend_from_slice(&0u16.to_le_bytes());
+
+  bytes.push(5);
+  bytes.extend_from_slice(b"test\0");
+
+  bytes.extend_from_slice(&(syscalls.len() as u16).to_le_bytes());
+  for (name, args) in syscalls {
+    bytes.push(*args);
+    bytes.push(name.len() as u8 + 1);
+    bytes.extend_from_slice(name.as_bytes());
+    bytes.push(0);
+  }
+
+  bytes
+}
+
+#[rustfmt::skip]
+const IF_ELSE_CODE: &[u8] = &[
+  0x01, 0x00, 0x00,                   // 0x04: init_stack 0, 0
+  0x0f, 0x00, 0x00,                   // 0x07: push_global 0
+  0x0c, 0x01,                         // 0x0a: push_i8 1
+  0x22,                               // 0x0c: sete
+  0x07, 0x1f, 0x00, 0x00, 0x00,       // 0x0d: jz 0x1f
+  0x0e, 0x03, b'h', b'i', 0x00,       // 0x12: push_string "hi"
+  0x03, 0x00, 0x00,                   // 0x17: syscall 0
+  0x06, 0x24, 0x00, 0x00, 0x00,       // 0x1a: jmp 0x24
+  0x0c, 0x02,                         // 0x1f: push_i8 2
+  0x15, 0x01, 0x00,                   // 0x21: pop_global 1
+  0x0c, 0x05,                         // 0x24: push_i8 5
+  0x02, 0x30, 0x00, 0x00, 0x00,       // 0x26: call 0x30
+  0x14,                               // 0x2b: push_return
+  0x15, 0x02, 0x00,                   // 0x2c: pop_global 2
+  0x04,                               // 0x2f: ret
+  0x01, 0x01, 0x00,                   // 0x30: init_stack 1, 0
+  0x10, 0x00,                         // 0x33: push_stack 0
+  0x0c, 0x02,                         // 0x35: push_i8 2
+  0x1c,                               // 0x37: mul
+  0x05,                               // 0x38: retv
+];
+
+#[rustfmt::skip]
+const WHILE_CODE: &[u8] = &[
+  0x01, 0x00, 0x01,                   // 0x04: init_stack 0, 1
+  0x10, 0x00,                         // 0x07: push_stack 0
+  0x0c, 0x03,                         // 0x09: push_i8 3
+  0x26,                               // 0x0b: setl
+  0x07, 0x1d, 0x00, 0x00, 0x00,       // 0x0c: jz 0x1d
+  0x10, 0x00,                         // 0x11: push_stack 0
+  0x0c, 0x01,                         // 0x13: push_i8 1
+  0x1a,                               // 0x15: add
+  0x16, 0x00,                         // 0x16: pop_stack 0
+  0x06, 0x07, 0x00, 0x00, 0x00,       // 0x18: jmp 0x07
+  0x04,                               // 0x1d: ret
+];
+
+#[test]
+fn parse_hcb_script() {
+  let hcb = FvpHcb::parse(build_hcb(IF_ELSE_CODE, &[("TextPrint", 1)])).unwrap();
+
+  assert_eq!(hcb.title(), "test");
+  assert_eq!(hcb.entry_point(), 4);
+  assert_eq!(hcb.non_volatile_global_count(), 3);
+  assert_eq!(hcb.syscalls().len(), 1);
+  assert_eq!(hcb.syscall_name(0), "TextPrint");
+
+  let instructions = hcb.instructions();
+  assert_eq!(instructions.len(), 20);
+  assert_eq!(
+    instructions[5].opcode,
+    FvpHcbOpcode::PushString("hi".to_string())
+  );
+
+  let functions = hcb.functions();
+  assert_eq!(functions.len(), 2);
+  assert_eq!(functions[0].address, 0x04);
+  assert_eq!(functions[0].instructions.len(), 15);
+  assert_eq!(functions[1].address, 0x30);
+  assert_eq!(functions[1].args, 1);
+}
+
+#[test]
+fn decompile_if_else_and_call() {
+  let hcb = FvpHcb::parse(build_hcb(IF_ELSE_CODE, &[("TextPrint", 1)])).unwrap();
+
+  assert_eq!(
+    hcb.decompile().to_string(),
+    "\
+// title: test
+// globals: 3 non-volatile, 0 volatile
+
+// entry point
+function sub_00000004() { // args: 0, locals: 0
+  if (global[0] == 1) {
+    TextPrint(\"hi\");
+  } else {
+    global[1] = 2;
+  }
+  global[2] = sub_00000030(5);
+  return;
+}
+
+function sub_00000030() { // args: 1, locals: 0
+  return local[0] * 2;
+}
+"
+  );
+}
+
+#[test]
+fn decompile_while_loop() {
+  let hcb = FvpHcb::parse(build_hcb(WHILE_CODE, &[])).unwrap();
+  let script = hcb.decompile();
+
+  assert_eq!(
+    script.to_string(),
+    "\
+// title: test
+// globals: 3 non-volatile, 0 volatile
+
+// entry point
+function sub_00000004() { // args: 0, locals: 1
+  while (local[0] < 3) {
+    local[0] = local[0] + 1;
+  }
+  return;
+}
+"
+  );
+}
+
+#[rustfmt::skip]
+const VOICE_CODE: &[u8] = &[
+  0x01, 0x00, 0x00,                   // 0x04: init_stack 0, 0
+  0x0c, 0x00,                         // 0x07: push_i8 0
+  0x0e, 0x0e, b'v', b'o', b'i', b'c', // 0x09: push_string "voice/yuk0001"
+  b'e', b'/', b'y', b'u', b'k', b'0',
+  b'0', b'0', b'1', 0x00,
+  0x03, 0x00, 0x00,                   // 0x19: syscall 0
+  0x0e, 0x06, b'h', b'e', b'l', b'l', // 0x1c: push_string "hello"
+  b'o', 0x00,
+  0x03, 0x01, 0x00,                   // 0x24: syscall 1
+  0x0e, 0x04, b'b', b'y', b'e', 0x00, // 0x27: push_string "bye"
+  0x03, 0x01, 0x00,                   // 0x2d: syscall 1
+  0x04,                               // 0x30: ret
+];
+
+#[test]
+fn map_voice_lines() {
+  let hcb = FvpHcb::parse(build_hcb(VOICE_CODE, &[("AudioLoad", 2), ("TextPrint", 1)])).unwrap();
+
+  let lines = hcb.decompile().voice_lines(&FvpVoiceSyscalls::default());
+
+  assert_eq!(lines.len(), 2);
+
+  assert_eq!(lines[0].function, 0x04);
+  assert_eq!(lines[0].speaker, None);
+  assert_eq!(lines[0].text, "hello");
+  assert_eq!(lines[0].voice.as_deref(), Some("voice/yuk0001"));
+  assert_eq!(lines[0].voice_entry_name(), Some("yuk0001"));
+
+  assert_eq!(lines[1].text, "bye");
+  assert_eq!(lines[1].voice, None);
+}
+
+#[test]
+fn collect_asset_references() {
+  let hcb = FvpHcb::parse(build_hcb(VOICE_CODE, &[("AudioLoad", 2), ("TextPrint", 1)])).unwrap();
+
+  let references = hcb.decompile().asset_references(&["AudioLoad"]);
+
+  assert_eq!(references.len(), 1);
+  assert_eq!(references[0].function, 0x04);
+  assert_eq!(references[0].syscall, "AudioLoad");
+  assert_eq!(references[0].entry_name(), "yuk0001");
+  assert_eq!(references[0].indices, [0]);
+}
+
+#[rustfmt::skip]
+const GRAPH_CODE: &[u8] = &[
+  0x01, 0x00, 0x00,                   // 0x04: init_stack 0, 0
+  0x0c, 0x00,                         // 0x07: push_i8 0
+  0x0e, 0x0c, b'g', b'r', b'a', b'p', // 0x09: push_string "graph/bg001"
+  b'h', b'/', b'b', b'g', b'0', b'0',
+  b'1', 0x00,
+  0x03, 0x00, 0x00,                   // 0x17: syscall 0
+  0x0c, 0x01,                         // 0x1a: push_i8 1
+  0x0e, 0x0c, b'g', b'r', b'a', b'p', // 0x1c: push_string "graph/ev002"
+  b'h', b'/', b'e', b'v', b'0', b'0',
+  b'2', 0x00,
+  0x03, 0x00, 0x00,                   // 0x2a: syscall 0
+  0x0e, 0x06, b'h', b'e', b'l', b'l', // 0x2d: push_string "hello"
+  b'o', 0x00,
+  0x03, 0x01, 0x00,                   // 0x35: syscall 1
+  0x04,                               // 0x38: ret
+];
+
+#[test]
+fn collect_image_references() {
+  let hcb = FvpHcb::parse(build_hcb(GRAPH_CODE, &[("GraphLoad", 2), ("TextPrint", 1)])).unwrap();
+
+  let references = hcb.decompile().asset_references(DEFAULT_IMAGE_SYSCALLS);
+
+  let images: BTreeSet<_> = references.iter().map(|x| x.entry_name()).collect();
+  assert_eq!(images, BTreeSet::from(["bg001", "ev002"]));
+  assert_eq!(references[1].indices, [1]);
+
+  let mut arc = FvpBin::default();
+  for name in ["bg001", "bg002", "ev001", "ev002"] {
+    arc.add_entry(FvpBinEntry::new(name, []));
+  }
+
+  let unreferenced: Vec<_> = unreferenced_entries(&arc, &references)
+    .iter()
+    .map(|entry| entry.filename())
+    .collect();
+  assert_eq!(unreferenced, ["bg002", "ev001"]);
+}
+
+#[test]
+fn build_call_graph() {
+  let hcb = FvpHcb::parse(build_hcb(IF_ELSE_CODE, &[("TextPrint", 1)])).unwrap();
+  let graph = hcb.decompile().call_graph();
+
+  assert_eq!(graph.entry_point, 0x04);
+  assert_eq!(graph.functions.len(), 2);
+
+  let main = &graph.functions[0];
+  assert_eq!(main.calls.iter().copied().collect::<Vec<_>>(), [0x30]);
+  assert_eq!(main.syscalls.iter().collect::<Vec<_>>(), ["TextPrint"]);
+  assert_eq!(main.reads.iter().copied().collect::<Vec<_>>(), [0]);
+  assert_eq!(main.writes.iter().copied().collect::<Vec<_>>(), [1, 2]);
+
+  let mut dot = Vec::new();
+  graph.write_dot(&mut dot).unwrap();
+  let dot = String::from_utf8(dot).unwrap();
+  assert!(dot.contains("\"sub_00000004\" -> \"sub_00000030\";"));
+}